@@ -0,0 +1,448 @@
+// ========================================
+// App State Export / Import
+// ========================================
+
+use crate::http_client::SharedHttpClient;
+use crate::secrets;
+use crate::settings::{AppSettings, SettingsStore};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+/// Bumped when the bundle layout changes incompatibly
+const STATE_FORMAT_VERSION: u32 = 1;
+
+/// Frontend storage key holding the saved VPN config path
+const VPN_CONFIG_PATH_KEY: &str = "vpn/configPath";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledVpnConfig {
+    pub original_path: String,
+    pub file_name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledLogo {
+    /// Cache file name (hash of the logo URL)
+    pub name: String,
+    pub data_url: String,
+}
+
+/// Everything `export_state` writes to the archive
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateBundle {
+    pub format_version: u32,
+    pub app_version: String,
+    pub exported_at: u64,
+    pub settings: AppSettings,
+    /// Frontend persisted storage (playlists, VPN profile, UI preferences) by key
+    pub storage: Map<String, Value>,
+    pub vpn_configs: Vec<BundledVpnConfig>,
+    /// `None` when the logo cache wasn't exported
+    pub logos: Option<Vec<BundledLogo>>,
+    /// Whether the fields in `sensitive` hold their values or were blanked
+    pub secrets_included: bool,
+    /// Secret fields, as JSON pointers into `storage`, `settings/<pointer>` or
+    /// `vpn_configs/<file name>`
+    pub sensitive: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub secrets_included: bool,
+    pub sensitive: Vec<String>,
+    pub vpn_configs: usize,
+    pub logos: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportedState {
+    /// Storage for the frontend to write back before reloading
+    pub storage: Map<String, Value>,
+    pub settings: AppSettings,
+    pub vpn_configs: Vec<String>,
+    pub logos: usize,
+    /// Secrets the backup didn't contain, which have to be entered again
+    pub missing_secrets: Vec<String>,
+}
+
+fn pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Record secret fields under `value`, blanking them when `redact` is set
+fn collect_sensitive(value: &mut Value, pointer: &str, redact: bool, found: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                let child_pointer = format!("{}/{}", pointer, pointer_segment(key));
+                let has_value = !child.is_null() && child.as_str() != Some("");
+                if secrets::is_sensitive_key(key) && has_value {
+                    found.push(child_pointer);
+                    if redact {
+                        *child = Value::Null;
+                    }
+                } else {
+                    collect_sensitive(child, &child_pointer, redact, found);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                collect_sensitive(child, &format!("{}/{}", pointer, i), redact, found);
+            }
+        }
+        Value::String(text) => {
+            if let Some(stripped) = secrets::redact_url(text, "") {
+                found.push(pointer.to_string());
+                if redact {
+                    *text = stripped;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Header names whose values in `header_rules` are credentials
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.trim().to_lowercase();
+    name.ends_with("authorization") || name == "cookie" || name.contains("token") || name.contains("key")
+}
+
+/// Fill header credentials blanked on export with the current value of the same header
+/// in a rule for the same host pattern, or drop the header so an empty one is never sent.
+/// Returns the `sensitive` pointers that had no current value.
+fn restore_blanked_headers(incoming: &mut AppSettings, current: &AppSettings, sensitive: &[String]) -> Vec<String> {
+    let mut unfilled = Vec::new();
+    for (i, rule) in incoming.header_rules.iter_mut().enumerate() {
+        let existing = current.header_rules.iter().find(|r| r.host_pattern == rule.host_pattern);
+        let headers = std::mem::take(&mut rule.headers);
+        for (j, (name, value)) in headers.into_iter().enumerate() {
+            let pointer = format!("settings/header_rules/{}/headers/{}/1", i, j);
+            if !value.is_empty() || !sensitive.contains(&pointer) {
+                rule.headers.push((name, value));
+                continue;
+            }
+            let current_value = existing
+                .and_then(|r| r.headers.iter().find(|(n, v)| n.eq_ignore_ascii_case(&name) && !v.is_empty()))
+                .map(|(_, v)| v.clone());
+            match current_value {
+                Some(value) => rule.headers.push((name, value)),
+                None => unfilled.push(pointer),
+            }
+        }
+    }
+    unfilled
+}
+
+/// Record credential values in the settings' header rules as `settings/...` pointers,
+/// blanking them when `redact` is set
+fn collect_sensitive_headers(settings: &mut AppSettings, redact: bool, found: &mut Vec<String>) {
+    for (i, rule) in settings.header_rules.iter_mut().enumerate() {
+        for (j, (name, value)) in rule.headers.iter_mut().enumerate() {
+            if is_sensitive_header(name) && !value.is_empty() {
+                found.push(format!("settings/header_rules/{}/headers/{}/1", i, j));
+                if redact {
+                    value.clear();
+                }
+            }
+        }
+    }
+}
+
+/// Fill what `existing` lacks from `imported`. Existing values win, arrays of objects
+/// with an `id` are merged per id and other arrays gain the entries they don't have.
+fn merge_value(existing: &mut Value, imported: Value) {
+    match (existing, imported) {
+        (Value::Object(current), Value::Object(incoming)) => {
+            for (key, value) in incoming {
+                match current.get_mut(&key) {
+                    Some(slot) if !slot.is_null() => merge_value(slot, value),
+                    _ => {
+                        current.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(current), Value::Array(incoming)) => {
+            let has_id = |v: &Value| v.get("id").is_some_and(|id| !id.is_null());
+            let by_id = current.iter().all(has_id) && incoming.iter().all(has_id);
+            for value in incoming {
+                if by_id {
+                    match current.iter_mut().find(|c| c.get("id") == value.get("id")) {
+                        Some(slot) => merge_value(slot, value),
+                        None => current.push(value),
+                    }
+                } else if !current.contains(&value) {
+                    current.push(value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn logo_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_cache_dir()
+        .map(|dir| dir.join("logos"))
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))
+}
+
+async fn read_logos(dir: &Path) -> Vec<BundledLogo> {
+    let mut logos = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return logos;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.ends_with(".datauri") {
+            continue;
+        }
+        if let Ok(data_url) = tokio::fs::read_to_string(entry.path()).await {
+            logos.push(BundledLogo { name, data_url });
+        }
+    }
+    logos
+}
+
+/// Write bundle files with owner-only permissions since they can hold keys
+async fn write_private(path: &Path, content: &str) -> Result<(), String> {
+    tokio::fs::write(path, content).await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await;
+    }
+    Ok(())
+}
+
+/// Bundle settings, the frontend `storage` (its persisted entries by key), the saved VPN
+/// config and optionally the logo cache into one JSON archive at `path`. Secrets are
+/// listed in the archive and left out unless `include_secrets` is set.
+#[tauri::command]
+pub async fn export_state(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    path: String,
+    storage: Map<String, Value>,
+    include_secrets: Option<bool>,
+    include_logos: Option<bool>,
+) -> Result<ExportSummary, String> {
+    let secrets_included = include_secrets.unwrap_or(false);
+    log::info!("Exporting app state to {} (secrets: {})", path, secrets_included);
+
+    let mut storage = Value::Object(storage);
+    let mut sensitive = Vec::new();
+    collect_sensitive(&mut storage, "", !secrets_included, &mut sensitive);
+    let Value::Object(storage) = storage else {
+        unreachable!("storage is an object");
+    };
+    let mut app_settings = settings.get();
+    collect_sensitive_headers(&mut app_settings, !secrets_included, &mut sensitive);
+
+    // VPN configs carry private keys, so the whole file counts as a secret
+    let mut vpn_configs = Vec::new();
+    if let Some(config_path) = storage.get(VPN_CONFIG_PATH_KEY).and_then(|v| v.as_str()) {
+        let file_name = Path::new(config_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "tunnel.conf".to_string());
+        sensitive.push(format!("vpn_configs/{}", file_name));
+        if secrets_included {
+            match tokio::fs::read_to_string(config_path).await {
+                Ok(content) => vpn_configs.push(BundledVpnConfig {
+                    original_path: config_path.to_string(),
+                    file_name,
+                    content,
+                }),
+                Err(e) => log::warn!("Skipping VPN config {}: {}", config_path, e),
+            }
+        }
+    }
+
+    let logos = match include_logos.unwrap_or(false) {
+        true => Some(read_logos(&logo_dir(&app)?).await),
+        false => None,
+    };
+
+    let bundle = StateBundle {
+        format_version: STATE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        settings: app_settings,
+        storage,
+        vpn_configs,
+        logos,
+        secrets_included,
+        sensitive,
+    };
+    let content = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize app state: {}", e))?;
+    write_private(Path::new(&path), &content).await?;
+
+    let summary = ExportSummary {
+        path,
+        secrets_included,
+        sensitive: bundle.sensitive,
+        vpn_configs: bundle.vpn_configs.len(),
+        logos: bundle.logos.as_ref().map_or(0, |l| l.len()),
+    };
+    log::info!(
+        "Exported app state: {} storage keys, {} VPN configs, {} logos, {} sensitive fields",
+        bundle.storage.len(), summary.vpn_configs, summary.logos, summary.sensitive.len()
+    );
+    Ok(summary)
+}
+
+/// Pick a file name in `dir` that doesn't clash with a different existing file
+async fn restore_target(dir: &Path, file_name: &str, content: &str, overwrite: bool) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if overwrite {
+        return candidate;
+    }
+    let stem = Path::new(file_name).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = Path::new(file_name).extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let mut n = 0;
+    loop {
+        let path = match n {
+            0 => candidate.clone(),
+            _ => dir.join(format!("{}-{}{}", stem, n, extension)),
+        };
+        match tokio::fs::read_to_string(&path).await {
+            Ok(existing) if existing != content => n += 1,
+            _ => return path,
+        }
+    }
+}
+
+/// Restore an `export_state` archive. With `merge` the backup only adds what's missing
+/// from `current` (the frontend's storage) and settings are kept; otherwise it replaces
+/// them. Returns the storage the frontend should persist.
+#[tauri::command]
+pub async fn import_state(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    shared_client: State<'_, SharedHttpClient>,
+    path: String,
+    merge: bool,
+    current: Option<Map<String, Value>>,
+) -> Result<ImportedState, String> {
+    log::info!("Importing app state from {} (merge: {})", path, merge);
+
+    let content = tokio::fs::read_to_string(&path).await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let bundle: StateBundle = serde_json::from_str(&content)
+        .map_err(|e| format!("Not a ClaudeTV state archive: {}", e))?;
+    if bundle.format_version > STATE_FORMAT_VERSION {
+        return Err(format!(
+            "Archive format {} is newer than this version of ClaudeTV supports ({})",
+            bundle.format_version, STATE_FORMAT_VERSION
+        ));
+    }
+
+    let mut storage = match merge {
+        true => Value::Object(current.unwrap_or_default()),
+        false => Value::Object(Map::new()),
+    };
+    merge_value(&mut storage, Value::Object(bundle.storage));
+    let Value::Object(mut storage) = storage else {
+        unreachable!("storage is an object");
+    };
+
+    // Restore configs next to the app data, since the original path may not exist here
+    let mut vpn_configs = Vec::new();
+    if !bundle.vpn_configs.is_empty() {
+        let dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+            .join("vpn");
+        tokio::fs::create_dir_all(&dir).await
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        for config in &bundle.vpn_configs {
+            let file_name = Path::new(&config.file_name)
+                .file_name()
+                .ok_or_else(|| format!("Invalid VPN config name '{}'", config.file_name))?;
+            let target = restore_target(&dir, &file_name.to_string_lossy(), &config.content, !merge).await;
+            write_private(&target, &config.content).await?;
+            let restored = target.to_string_lossy().to_string();
+            if storage.get(VPN_CONFIG_PATH_KEY).and_then(|v| v.as_str()) == Some(config.original_path.as_str()) {
+                storage.insert(VPN_CONFIG_PATH_KEY.to_string(), Value::String(restored.clone()));
+            }
+            vpn_configs.push(restored);
+        }
+    }
+
+    let mut logos = 0;
+    if let Some(bundled) = &bundle.logos {
+        let dir = logo_dir(&app)?;
+        if !merge {
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+        }
+        tokio::fs::create_dir_all(&dir).await
+            .map_err(|e| format!("Failed to create logo cache: {}", e))?;
+        for logo in bundled {
+            let valid_name = crate::stream_proxy::is_safe_file_name(&logo.name) && logo.name.ends_with(".datauri");
+            let target = dir.join(&logo.name);
+            if !valid_name || (merge && target.exists()) {
+                continue;
+            }
+            if tokio::fs::write(&target, &logo.data_url).await.is_ok() {
+                logos += 1;
+            }
+        }
+    }
+
+    // Header credentials blanked on export that couldn't be filled from the current settings
+    let mut unfilled_headers = Vec::new();
+    let settings = match merge {
+        true => settings.get(),
+        false => {
+            let updated = settings.update(|s| {
+                let mut incoming = bundle.settings;
+                if !bundle.secrets_included {
+                    unfilled_headers = restore_blanked_headers(&mut incoming, s, &bundle.sensitive);
+                }
+                *s = incoming;
+            })?;
+            shared_client.rebuild(&updated)?;
+            updated
+        }
+    };
+
+    // After a merge the existing data may already hold some of the left-out secrets
+    let missing_secrets: Vec<String> = match bundle.secrets_included {
+        true => Vec::new(),
+        false => {
+            let restored = Value::Object(storage.clone());
+            bundle.sensitive.into_iter()
+                .filter(|pointer| match pointer.strip_prefix("vpn_configs/") {
+                    Some(_) => vpn_configs.is_empty(),
+                    None if pointer.starts_with("settings/") => unfilled_headers.contains(pointer),
+                    None => match restored.pointer(pointer) {
+                        None | Some(Value::Null) => true,
+                        Some(Value::String(text)) => text.starts_with("http") && secrets::redact_url(text, "").is_none(),
+                        Some(_) => false,
+                    },
+                })
+                .collect()
+        }
+    };
+    log::info!(
+        "Imported app state: {} storage keys, {} VPN configs, {} logos, {} secrets to re-enter",
+        storage.len(), vpn_configs.len(), logos, missing_secrets.len()
+    );
+    Ok(ImportedState {
+        storage,
+        settings,
+        vpn_configs,
+        logos,
+        missing_secrets,
+    })
+}
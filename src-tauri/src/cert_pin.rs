@@ -0,0 +1,118 @@
+// ========================================
+// TLS Certificate Pinning
+// ========================================
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+
+/// Expected SHA-256 fingerprint of a host's leaf certificate. After a failed
+/// handshake, `observed` holds the fingerprint the server actually presented.
+#[derive(Debug, Clone)]
+pub struct CertPin {
+    pub expected: String,
+    observed: Arc<Mutex<Option<String>>>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl CertPin {
+    /// Accepts hex with or without `:` separators, in either case
+    pub fn parse(fingerprint: &str) -> Result<Self, String> {
+        let expected: String = fingerprint.trim()
+            .chars()
+            .filter(|c| *c != ':')
+            .collect::<String>()
+            .to_lowercase();
+        if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "Invalid certificate fingerprint '{}': expected 64 hex characters (SHA-256)",
+                fingerprint
+            ));
+        }
+        Ok(Self {
+            expected,
+            observed: Arc::default(),
+        })
+    }
+
+    /// Fingerprint the server presented if the last handshake failed the pin
+    pub fn mismatch(&self) -> Option<String> {
+        self.observed.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// rustls configuration that trusts exactly the pinned certificate, with the
+    /// ALPN offer matching `http_version` ("auto", "http1" or "http2")
+    pub fn tls_config(&self, http_version: &str) -> Result<rustls::ClientConfig, String> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier = PinnedCertVerifier {
+            pin: self.clone(),
+            provider: provider.clone(),
+        };
+        let mut config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| format!("Failed to configure TLS: {}", e))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        config.alpn_protocols = match http_version {
+            "http1" => vec![b"http/1.1".to_vec()],
+            "http2" => vec![b"h2".to_vec()],
+            _ => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+        };
+        Ok(config)
+    }
+}
+
+/// Replaces CA validation: the leaf must be the pinned certificate. Intermediates are
+/// not accepted as pins, since without a chain check any leaf could be sent alongside them.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    pin: CertPin,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint = hex(&Sha256::digest(end_entity.as_ref()));
+        if fingerprint == self.pin.expected {
+            return Ok(ServerCertVerified::assertion());
+        }
+        *self.pin.observed.lock().unwrap_or_else(|e| e.into_inner()) = Some(fingerprint);
+        Err(rustls::Error::General("certificate does not match the pinned fingerprint".to_string()))
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
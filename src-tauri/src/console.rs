@@ -0,0 +1,128 @@
+// ========================================
+// In-App Console
+// ========================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use tauri::ipc::Channel;
+
+/// Entries kept for consoles opened after the fact
+const CONSOLE_BUFFER_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConsoleLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsoleEntry {
+    pub seq: u64,
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    pub level: ConsoleLevel,
+    /// Subsystem the entry came from, e.g. "vpn", "http", "ffmpeg"
+    pub source: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsoleSubscription {
+    pub subscription_id: u64,
+    /// Buffered entries at or above the requested level, oldest first
+    pub backlog: Vec<ConsoleEntry>,
+}
+
+struct Subscriber {
+    id: u64,
+    min_level: ConsoleLevel,
+    channel: Channel<ConsoleEntry>,
+}
+
+#[derive(Default)]
+struct ConsoleState {
+    next_seq: u64,
+    next_subscriber: u64,
+    entries: VecDeque<ConsoleEntry>,
+    subscribers: Vec<Subscriber>,
+}
+
+fn console() -> &'static Mutex<ConsoleState> {
+    static CONSOLE: OnceLock<Mutex<ConsoleState>> = OnceLock::new();
+    CONSOLE.get_or_init(Default::default)
+}
+
+/// Log a message and publish it to the in-app console. Credentials are redacted first,
+/// since messages carry request URLs and FFmpeg output.
+pub fn record(level: ConsoleLevel, source: &str, message: impl Into<String>) {
+    let message = crate::diagnostics::redact(&message.into());
+    let log_level = match level {
+        ConsoleLevel::Debug => log::Level::Debug,
+        ConsoleLevel::Info => log::Level::Info,
+        ConsoleLevel::Warn => log::Level::Warn,
+        ConsoleLevel::Error => log::Level::Error,
+    };
+    log::log!(log_level, "[{}] {}", source, message);
+
+    let mut state = console().lock().unwrap_or_else(|e| e.into_inner());
+    let entry = ConsoleEntry {
+        seq: state.next_seq,
+        timestamp_ms: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default(),
+        level,
+        source: source.to_string(),
+        message,
+    };
+    state.next_seq += 1;
+
+    // Drop subscribers whose webview went away
+    state.subscribers.retain(|subscriber| {
+        subscriber.min_level > level || subscriber.channel.send(entry.clone()).is_ok()
+    });
+
+    if state.entries.len() >= CONSOLE_BUFFER_ENTRIES {
+        state.entries.pop_front();
+    }
+    state.entries.push_back(entry);
+}
+
+/// Stream console entries at or above `level` (default Info) to `on_entry`
+#[tauri::command]
+pub fn subscribe_console(
+    level: Option<ConsoleLevel>,
+    on_entry: Channel<ConsoleEntry>,
+) -> ConsoleSubscription {
+    let min_level = level.unwrap_or(ConsoleLevel::Info);
+    let mut state = console().lock().unwrap_or_else(|e| e.into_inner());
+
+    let backlog = state.entries.iter()
+        .filter(|entry| entry.level >= min_level)
+        .cloned()
+        .collect();
+    let subscription_id = state.next_subscriber;
+    state.next_subscriber += 1;
+    state.subscribers.push(Subscriber {
+        id: subscription_id,
+        min_level,
+        channel: on_entry,
+    });
+
+    ConsoleSubscription {
+        subscription_id,
+        backlog,
+    }
+}
+
+/// Stop streaming to a console opened with `subscribe_console`
+#[tauri::command]
+pub fn unsubscribe_console(subscription_id: u64) -> bool {
+    let mut state = console().lock().unwrap_or_else(|e| e.into_inner());
+    let before = state.subscribers.len();
+    state.subscribers.retain(|subscriber| subscriber.id != subscription_id);
+    state.subscribers.len() != before
+}
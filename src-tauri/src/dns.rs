@@ -0,0 +1,184 @@
+// ========================================
+// VPN DNS Resolution
+// ========================================
+
+use rand::Rng;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+/// Per-server wait for an answer before trying the next one
+const DNS_TIMEOUT_MS: u64 = 3000;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+/// Build a recursive query for `host` with the given record type
+fn build_query(id: u16, host: &str, record_type: u16) -> Result<Vec<u8>, String> {
+    let mut packet = Vec::with_capacity(32 + host.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Standard query, recursion desired, one question
+    packet.extend_from_slice(&[0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("Invalid host name: {}", host));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&record_type.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes());
+    Ok(packet)
+}
+
+/// Offset just past a (possibly compressed) name starting at `pos`
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)?;
+        match len {
+            0 => return Some(pos + 1),
+            // Compression pointer ends the name
+            l if l & 0xC0 == 0xC0 => return Some(pos + 2),
+            l => pos += 1 + l as usize,
+        }
+    }
+}
+
+fn read_u16(packet: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]))
+}
+
+/// Whether `packet` answers `query`: same id and the same single question. Names are
+/// compared ignoring case since servers may echo them in a different case.
+fn answers_query(query: &[u8], packet: &[u8]) -> bool {
+    let question = &query[12..];
+    packet.get(0..2) == query.get(0..2)
+        && read_u16(packet, 4) == Some(1)
+        && packet.get(12..12 + question.len()).is_some_and(|q| q.eq_ignore_ascii_case(question))
+}
+
+/// Addresses from the answer section of a response that passed `answers_query`
+fn parse_response(packet: &[u8]) -> Result<Vec<IpAddr>, String> {
+    let malformed = || "Malformed DNS response".to_string();
+    let flags = read_u16(packet, 2).ok_or_else(malformed)?;
+    if flags & 0x0200 != 0 {
+        return Err("DNS response was truncated".to_string());
+    }
+    match flags & 0x000F {
+        0 => {}
+        // NXDOMAIN is an answer, just an empty one
+        3 => return Ok(Vec::new()),
+        code => return Err(format!("DNS server returned error code {}", code)),
+    }
+
+    let questions = read_u16(packet, 4).ok_or_else(malformed)?;
+    let answers = read_u16(packet, 6).ok_or_else(malformed)?;
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(packet, pos).ok_or_else(malformed)? + 4;
+    }
+
+    let mut addresses = Vec::new();
+    for _ in 0..answers {
+        pos = skip_name(packet, pos).ok_or_else(malformed)?;
+        let record_type = read_u16(packet, pos).ok_or_else(malformed)?;
+        let rdlength = read_u16(packet, pos + 8).ok_or_else(malformed)? as usize;
+        let rdata = packet.get(pos + 10..pos + 10 + rdlength).ok_or_else(malformed)?;
+        match (record_type, rdata.len()) {
+            (TYPE_A, 4) => addresses.push(IpAddr::V4(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]))),
+            (TYPE_AAAA, 16) => {
+                let octets: [u8; 16] = rdata.try_into().map_err(|_| malformed())?;
+                addresses.push(IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            // CNAMEs are followed by the server in the same answer
+            _ => {}
+        }
+        pos += 10 + rdlength;
+    }
+    Ok(addresses)
+}
+
+async fn query(server: IpAddr, host: &str, record_type: u16) -> Result<Vec<IpAddr>, String> {
+    // Random id and source port, so an off-path attacker has to guess both
+    let packet = build_query(rand::random(), host, record_type)?;
+    let socket = bind_random_port(server).await?;
+    socket.connect((server, 53)).await
+        .map_err(|e| format!("Failed to reach DNS server {}: {}", server, e))?;
+    socket.send(&packet).await
+        .map_err(|e| format!("Failed to query DNS server {}: {}", server, e))?;
+
+    // Drop datagrams that don't answer this query instead of failing on them
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_millis(DNS_TIMEOUT_MS);
+    let mut buf = [0u8; 1500];
+    loop {
+        let len = tokio::time::timeout_at(deadline, socket.recv(&mut buf))
+            .await
+            .map_err(|_| format!("DNS server {} did not answer", server))?
+            .map_err(|e| format!("DNS server {} failed: {}", server, e))?;
+        if answers_query(&packet, &buf[..len]) {
+            return parse_response(&buf[..len]);
+        }
+        log::warn!("Ignoring DNS response from {} that doesn't match the query", server);
+    }
+}
+
+/// UDP socket on a random unprivileged port, falling back to one the OS picks
+async fn bind_random_port(server: IpAddr) -> Result<UdpSocket, String> {
+    let any: IpAddr = match server {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    for _ in 0..8 {
+        let port = rand::thread_rng().gen_range(1024..=u16::MAX);
+        if let Ok(socket) = UdpSocket::bind(SocketAddr::new(any, port)).await {
+            return Ok(socket);
+        }
+    }
+    UdpSocket::bind(SocketAddr::new(any, 0)).await
+        .map_err(|e| format!("Failed to open DNS socket: {}", e))
+}
+
+/// Resolve `host` to A and AAAA records using only `servers`, in order
+pub async fn lookup(servers: &[IpAddr], host: &str) -> Result<Vec<IpAddr>, String> {
+    let mut last_error = "No DNS servers configured".to_string();
+    for &server in servers {
+        let (v4, v6) = tokio::join!(query(server, host, TYPE_A), query(server, host, TYPE_AAAA));
+        match (v4, v6) {
+            (Err(e), Err(_)) => last_error = e,
+            (v4, v6) => {
+                let addresses: Vec<IpAddr> = v4.unwrap_or_default().into_iter()
+                    .chain(v6.unwrap_or_default())
+                    .collect();
+                if addresses.is_empty() {
+                    return Err(format!("{} has no addresses on DNS server {}", host, server));
+                }
+                return Ok(addresses);
+            }
+        }
+    }
+    Err(last_error)
+}
+
+/// reqwest resolver that only asks the tunnel's DNS servers
+pub struct VpnDnsResolver {
+    servers: Arc<Vec<IpAddr>>,
+}
+
+impl VpnDnsResolver {
+    pub fn new(servers: Vec<IpAddr>) -> Self {
+        Self { servers: Arc::new(servers) }
+    }
+}
+
+impl reqwest::dns::Resolve for VpnDnsResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let servers = self.servers.clone();
+        Box::pin(async move {
+            let addresses = lookup(&servers, name.as_str()).await?;
+            // The port is filled in by the connector
+            let addrs: reqwest::dns::Addrs = Box::new(addresses.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
@@ -0,0 +1,302 @@
+// ========================================
+// File Downloads
+// ========================================
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+
+/// Minimum time between progress events
+const PROGRESS_INTERVAL_MS: u128 = 250;
+
+/// Suffix of the file next to a partial download that stores its `If-Range` validator
+const VALIDATOR_SUFFIX: &str = ".resume.json";
+
+#[derive(Debug, Clone, Serialize)]
+struct DownloadProgressEvent {
+    url: String,
+    output_path: String,
+    downloaded: u64,
+    total: Option<u64>,
+    bytes_per_sec: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DownloadResult {
+    pub output_path: String,
+    /// Size of the file on disk when the download finished
+    pub bytes: u64,
+    /// Bytes transferred by this call (less than `bytes` when resumed)
+    pub bytes_transferred: u64,
+    pub resumed: bool,
+    pub resume_outcome: ResumeOutcome,
+}
+
+/// What happened to an existing partial file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResumeOutcome {
+    /// Nothing to resume, downloaded from the start
+    Fresh,
+    /// Continued after the server confirmed the file is unchanged (`If-Range`)
+    Resumed,
+    /// Continued without a stored validator, so a changed file can't be detected
+    ResumedUnverified,
+    /// The partial file already had every byte
+    AlreadyComplete,
+    /// The file changed since the partial download, restarted from scratch
+    RestartedChanged,
+    /// The server doesn't do byte ranges, restarted from scratch
+    RestartedNoRanges,
+}
+
+/// `ETag` / `Last-Modified` of the response a partial download started from
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ResumeValidator {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl ResumeValidator {
+    fn from_response(response: &reqwest::Response) -> Self {
+        let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+        Self {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        }
+    }
+
+    /// `If-Range` value: a strong ETag, else the date. Weak ETags aren't allowed there.
+    fn if_range(&self) -> Option<&str> {
+        self.etag.as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+fn validator_path(output_path: &str) -> String {
+    format!("{}{}", output_path, VALIDATOR_SUFFIX)
+}
+
+async fn read_validator(output_path: &str) -> Option<ResumeValidator> {
+    let content = tokio::fs::read(validator_path(output_path)).await.ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+/// Store the validator for a later resume, or drop a stale one when there is none
+async fn save_validator(output_path: &str, validator: &ResumeValidator) {
+    let path = validator_path(output_path);
+    let result = match validator.if_range() {
+        Some(_) => tokio::fs::write(&path, serde_json::to_vec(validator).unwrap_or_default()).await,
+        None => tokio::fs::remove_file(&path).await,
+    };
+    if let Err(e) = result {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to update {}: {}", path, e);
+        }
+    }
+}
+
+/// Total size from `Content-Range: bytes 100-199/200`
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    response.headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str().ok()?
+        .rsplit('/')
+        .next()?
+        .parse().ok()
+}
+
+/// First byte from `Content-Range: bytes 100-199/200`
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    response.headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str().ok()?
+        .trim()
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse().ok()
+}
+
+/// Check with a HEAD request whether the server accepts byte ranges
+async fn supports_ranges(client: &reqwest::Client, url: &str) -> bool {
+    match client.head(url).send().await {
+        Ok(response) => response.headers()
+            .get(reqwest::header::ACCEPT_RANGES)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("bytes")),
+        Err(e) => {
+            log::warn!("HEAD request failed for {}: {}", url, e);
+            false
+        }
+    }
+}
+
+/// Download `url` straight to `output_path` without buffering it in memory.
+/// With `resume`, an existing partial file is continued via a `Range` request
+/// when the server supports it, otherwise the download restarts from scratch.
+/// The `ETag`/`Last-Modified` of the first response is kept next to the file and sent
+/// as `If-Range`, so a file that changed upstream is downloaded again, not appended to.
+#[tauri::command]
+pub async fn download_file(
+    app: AppHandle,
+    url: String,
+    output_path: String,
+    resume: bool,
+) -> Result<DownloadResult, String> {
+    log::info!("Downloading {} to {} (resume: {})", url, output_path, resume);
+
+    // No overall timeout, large files can take a long time
+    let client = reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut existing = if resume {
+        tokio::fs::metadata(&output_path).await.map(|m| m.len()).unwrap_or(0)
+    } else {
+        0
+    };
+
+    let mut outcome = ResumeOutcome::Fresh;
+    if existing > 0 && !supports_ranges(&client, &url).await {
+        log::info!("Server doesn't support ranges, restarting download");
+        existing = 0;
+        outcome = ResumeOutcome::RestartedNoRanges;
+    }
+
+    let validator = match existing > 0 {
+        true => read_validator(&output_path).await,
+        false => None,
+    };
+    let if_range = validator.as_ref().and_then(ResumeValidator::if_range);
+    let mut request = client.get(&url);
+    if existing > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        if let Some(if_range) = if_range {
+            request = request.header(reqwest::header::IF_RANGE, if_range);
+        }
+    }
+
+    let mut response = request.send().await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    // 416 with `bytes */<total>`: complete only if the partial file has exactly that
+    // size. A larger one (or an unknown total) means the file changed upstream.
+    let mut restart = None;
+    if existing > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        if content_range_total(&response) == Some(existing) {
+            log::info!("{} is already fully downloaded", output_path);
+            let _ = tokio::fs::remove_file(validator_path(&output_path)).await;
+            return Ok(DownloadResult {
+                output_path,
+                bytes: existing,
+                bytes_transferred: 0,
+                resumed: true,
+                resume_outcome: ResumeOutcome::AlreadyComplete,
+            });
+        }
+        restart = Some(ResumeOutcome::RestartedChanged);
+    } else if existing > 0
+        && response.status() == reqwest::StatusCode::PARTIAL_CONTENT
+        && content_range_start(&response) != Some(existing)
+    {
+        // Appending bytes from another offset would corrupt the file
+        restart = Some(ResumeOutcome::RestartedNoRanges);
+    }
+    if let Some(restart) = restart {
+        log::info!("Can't resume {} at byte {}, restarting ({:?})", url, existing, restart);
+        existing = 0;
+        outcome = restart;
+        response = client.get(&url).send().await
+            .map_err(|e| format!("Request failed: {}", e))?;
+    }
+
+    if !response.status().is_success() {
+        return Err(format!("Download failed with status {}", response.status()));
+    }
+
+    // A 200 to a ranged request means the server sent the whole file: either the
+    // If-Range validator no longer matches or the server ignored the range
+    let resumed = existing > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    if resumed {
+        outcome = match if_range {
+            Some(_) => ResumeOutcome::Resumed,
+            None => ResumeOutcome::ResumedUnverified,
+        };
+    } else if existing > 0 {
+        outcome = match if_range {
+            Some(_) => ResumeOutcome::RestartedChanged,
+            None => ResumeOutcome::RestartedNoRanges,
+        };
+        log::info!("Restarting download of {} ({:?})", url, outcome);
+        existing = 0;
+    }
+    if !resumed {
+        save_validator(&output_path, &ResumeValidator::from_response(&response)).await;
+    }
+
+    let total = if resumed {
+        content_range_total(&response)
+    } else {
+        response.content_length()
+    };
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&output_path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", output_path, e))?;
+
+    let started = std::time::Instant::now();
+    let mut last_progress = started;
+    let mut transferred: u64 = 0;
+
+    while let Some(chunk) = response.chunk().await
+        .map_err(|e| format!("Download interrupted after {} bytes: {}", existing + transferred, e))?
+    {
+        file.write_all(&chunk).await
+            .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+        transferred += chunk.len() as u64;
+
+        if last_progress.elapsed().as_millis() >= PROGRESS_INTERVAL_MS {
+            last_progress = std::time::Instant::now();
+            let elapsed = started.elapsed().as_secs_f64().max(0.001);
+            let _ = app.emit("download://progress", DownloadProgressEvent {
+                url: url.clone(),
+                output_path: output_path.clone(),
+                downloaded: existing + transferred,
+                total,
+                bytes_per_sec: (transferred as f64 / elapsed) as u64,
+            });
+        }
+    }
+
+    file.flush().await
+        .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+
+    let elapsed = started.elapsed().as_secs_f64().max(0.001);
+    let _ = app.emit("download://progress", DownloadProgressEvent {
+        url: url.clone(),
+        output_path: output_path.clone(),
+        downloaded: existing + transferred,
+        total,
+        bytes_per_sec: (transferred as f64 / elapsed) as u64,
+    });
+
+    let _ = tokio::fs::remove_file(validator_path(&output_path)).await;
+    log::info!("Downloaded {} bytes to {} ({:?})", transferred, output_path, outcome);
+
+    Ok(DownloadResult {
+        output_path,
+        bytes: existing + transferred,
+        bytes_transferred: transferred,
+        resumed,
+        resume_outcome: outcome,
+    })
+}
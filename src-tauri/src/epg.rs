@@ -0,0 +1,319 @@
+// ========================================
+// XMLTV Program Guide
+// ========================================
+
+use crate::playlist::Channel;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpgChannel {
+    pub id: String,
+    pub display_names: Vec<String>,
+    pub icon: Option<String>,
+    pub programme_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpgProgramme {
+    pub channel: String,
+    /// XMLTV timestamps as written, e.g. `20240101060000 +0000`
+    pub start: String,
+    pub stop: Option<String>,
+    /// `start`/`stop` as Unix milliseconds, shifted by the channel's `tvg-shift` when matched
+    pub start_ms: Option<i64>,
+    pub stop_ms: Option<i64>,
+    pub title: String,
+    pub desc: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EpgGuide {
+    pub channels: Vec<EpgChannel>,
+    pub programmes: Vec<EpgProgramme>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum MatchMethod {
+    TvgId,
+    /// `tvg-id` matched after ignoring case
+    TvgIdCaseInsensitive,
+    /// Normalized channel name matched a guide display name
+    Name,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EpgMatch {
+    /// Index into the `channels` passed in
+    pub channel_index: usize,
+    pub channel_name: String,
+    pub epg_channel_id: String,
+    pub method: MatchMethod,
+    /// Hours applied to the guide times below
+    pub tvg_shift: Option<f32>,
+    pub programmes: Vec<EpgProgramme>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EpgMatchReport {
+    pub matches: Vec<EpgMatch>,
+    pub total_channels: usize,
+    /// Matched channels whose guide entry has at least one programme
+    pub channels_with_guide_data: usize,
+    /// Playlist `tvg-id`s that don't exist in the guide
+    pub missing_tvg_ids: Vec<String>,
+    /// Guide channels no playlist channel was matched to
+    pub unused_guide_channels: Vec<String>,
+}
+
+struct XmltvPatterns {
+    channel: Regex,
+    programme: Regex,
+    display_name: Regex,
+    icon: Regex,
+    title: Regex,
+    desc: Regex,
+}
+
+fn patterns() -> &'static XmltvPatterns {
+    static PATTERNS: OnceLock<XmltvPatterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| XmltvPatterns {
+        channel: Regex::new(r"(?s)<channel\b([^>]*?)(?:/>|>(.*?)</channel>)").unwrap(),
+        programme: Regex::new(r"(?s)<programme\b([^>]*?)(?:/>|>(.*?)</programme>)").unwrap(),
+        display_name: Regex::new(r"(?s)<display-name\b[^>]*>(.*?)</display-name>").unwrap(),
+        icon: Regex::new(r#"<icon\b[^>]*\bsrc\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap(),
+        title: Regex::new(r"(?s)<title\b[^>]*>(.*?)</title>").unwrap(),
+        desc: Regex::new(r"(?s)<desc\b[^>]*>(.*?)</desc>").unwrap(),
+    })
+}
+
+/// Decode CDATA sections and the predefined XML entities
+fn xml_text(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Some(inner) = raw.strip_prefix("<![CDATA[").and_then(|r| r.strip_suffix("]]>")) {
+        return inner.to_string();
+    }
+    raw.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// XMLTV time (`20240101060000 +0100`, offset optional) as Unix milliseconds
+fn xmltv_time_ms(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(time) = chrono::DateTime::parse_from_str(value, "%Y%m%d%H%M%S %z") {
+        return Some(time.timestamp_millis());
+    }
+    // No offset means UTC
+    let digits = value.get(..14)?;
+    chrono::NaiveDateTime::parse_from_str(digits, "%Y%m%d%H%M%S")
+        .ok()
+        .map(|time| time.and_utc().timestamp_millis())
+}
+
+/// Value of `name="..."` (or single quoted) in a tag's attribute string
+fn attribute(attrs: &str, name: &str) -> Option<String> {
+    let mut rest = attrs;
+    while let Some(pos) = rest.find(name) {
+        let preceded_ok = rest[..pos].chars().last().map_or(true, char::is_whitespace);
+        let after = rest[pos + name.len()..].trim_start();
+        if let (true, Some(after)) = (preceded_ok, after.strip_prefix('=')) {
+            let after = after.trim_start();
+            let quote = after.chars().next()?;
+            if quote == '"' || quote == '\'' {
+                let value = &after[1..];
+                let end = value.find(quote)?;
+                return Some(xml_text(&value[..end]));
+            }
+        }
+        rest = &rest[pos + name.len()..];
+    }
+    None
+}
+
+/// Parse an XMLTV document into its channels and programmes
+pub fn parse_xmltv_content(content: &str) -> EpgGuide {
+    let p = patterns();
+
+    let programmes: Vec<EpgProgramme> = p.programme.captures_iter(content)
+        .filter_map(|caps| {
+            let attrs = caps.get(1).map_or("", |m| m.as_str());
+            let body = caps.get(2).map_or("", |m| m.as_str());
+            let start = attribute(attrs, "start").unwrap_or_default();
+            let stop = attribute(attrs, "stop");
+            Some(EpgProgramme {
+                channel: attribute(attrs, "channel")?,
+                start_ms: xmltv_time_ms(&start),
+                stop_ms: stop.as_deref().and_then(xmltv_time_ms),
+                start,
+                stop,
+                title: p.title.captures(body).map(|c| xml_text(&c[1])).unwrap_or_default(),
+                desc: p.desc.captures(body).map(|c| xml_text(&c[1])),
+            })
+        })
+        .collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for programme in &programmes {
+        *counts.entry(programme.channel.as_str()).or_default() += 1;
+    }
+
+    let channels = p.channel.captures_iter(content)
+        .filter_map(|caps| {
+            let attrs = caps.get(1).map_or("", |m| m.as_str());
+            let body = caps.get(2).map_or("", |m| m.as_str());
+            let id = attribute(attrs, "id")?;
+            Some(EpgChannel {
+                display_names: p.display_name.captures_iter(body).map(|c| xml_text(&c[1])).collect(),
+                icon: p.icon.captures(body)
+                    .and_then(|c| c.get(1).or_else(|| c.get(2)))
+                    .map(|m| xml_text(m.as_str())),
+                programme_count: counts.get(id.as_str()).copied().unwrap_or(0),
+                id,
+            })
+        })
+        .collect();
+
+    EpgGuide { channels, programmes }
+}
+
+/// Parse an XMLTV guide, off the main thread since full guides run to hundreds of MB
+#[tauri::command]
+pub async fn parse_xmltv(content: String) -> Result<EpgGuide, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let guide = parse_xmltv_content(&content);
+        log::info!(
+            "Parsed XMLTV: {} channels, {} programmes ({} bytes)",
+            guide.channels.len(),
+            guide.programmes.len(),
+            content.len()
+        );
+        guide
+    })
+    .await
+    .map_err(|e| format!("XMLTV parsing failed: {}", e))
+}
+
+/// Tokens that differ between a playlist and its guide without meaning a different channel
+const NAME_NOISE: &[&str] = &["hd", "fhd", "uhd", "sd", "4k", "hevc", "h265"];
+
+/// Lowercase alphanumerics only, without quality suffixes: "BBC One HD" -> "bbcone"
+fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !NAME_NOISE.contains(token))
+        .collect()
+}
+
+/// Correlate playlist channels with guide channels by `tvg-id`, falling back to names,
+/// and report how much of the playlist the guide covers
+#[tauri::command]
+pub async fn match_epg_to_channels(channels: Vec<Channel>, xmltv: String) -> Result<EpgMatchReport, String> {
+    tauri::async_runtime::spawn_blocking(move || match_epg(&channels, &xmltv))
+        .await
+        .map_err(|e| format!("EPG matching failed: {}", e))
+}
+
+fn match_epg(channels: &[Channel], xmltv: &str) -> EpgMatchReport {
+    let guide = parse_xmltv_content(xmltv);
+
+    // Programmes grouped once, so each match doesn't scan the whole guide
+    let mut programmes_by_channel: HashMap<&str, Vec<&EpgProgramme>> = HashMap::new();
+    for programme in &guide.programmes {
+        programmes_by_channel.entry(programme.channel.as_str()).or_default().push(programme);
+    }
+
+    let by_id: HashMap<&str, &EpgChannel> = guide.channels.iter()
+        .map(|c| (c.id.as_str(), c))
+        .collect();
+    let by_lower_id: HashMap<String, &EpgChannel> = guide.channels.iter()
+        .map(|c| (c.id.to_lowercase(), c))
+        .collect();
+    // Display names and the id itself ("bbcone.uk" -> "bbcone") as name candidates
+    let mut by_name: HashMap<String, &EpgChannel> = HashMap::new();
+    for channel in &guide.channels {
+        let id_stem = channel.id.split('.').next().unwrap_or_default();
+        for name in channel.display_names.iter().map(String::as_str).chain([id_stem]) {
+            let key = normalize_name(name);
+            if !key.is_empty() {
+                by_name.entry(key).or_insert(channel);
+            }
+        }
+    }
+
+    let mut matches = Vec::new();
+    let mut missing_tvg_ids = Vec::new();
+    for (channel_index, channel) in channels.iter().enumerate() {
+        let tvg_id = channel.tvg_id.as_deref().filter(|id| !id.is_empty());
+        let found = tvg_id
+            .and_then(|id| by_id.get(id).map(|c| (*c, MatchMethod::TvgId)))
+            .or_else(|| tvg_id.and_then(|id| {
+                by_lower_id.get(&id.to_lowercase()).map(|c| (*c, MatchMethod::TvgIdCaseInsensitive))
+            }))
+            .or_else(|| {
+                [channel.tvg_name.as_deref(), Some(channel.name.as_str())]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|name| by_name.get(&normalize_name(name)))
+                    .map(|c| (*c, MatchMethod::Name))
+            });
+
+        if let Some(id) = tvg_id {
+            if !matches!(found, Some((_, MatchMethod::TvgId | MatchMethod::TvgIdCaseInsensitive))) {
+                missing_tvg_ids.push(id.to_string());
+            }
+        }
+        if let Some((epg_channel, method)) = found {
+            let shift_ms = channel.tvg_shift.map_or(0, |hours| (hours as f64 * 3_600_000.0) as i64);
+            let programmes = programmes_by_channel.get(epg_channel.id.as_str())
+                .into_iter()
+                .flatten()
+                .map(|p| EpgProgramme {
+                    start_ms: p.start_ms.map(|ms| ms + shift_ms),
+                    stop_ms: p.stop_ms.map(|ms| ms + shift_ms),
+                    ..(*p).clone()
+                })
+                .collect();
+            matches.push(EpgMatch {
+                channel_index,
+                channel_name: channel.name.clone(),
+                epg_channel_id: epg_channel.id.clone(),
+                method,
+                tvg_shift: channel.tvg_shift,
+                programmes,
+            });
+        }
+    }
+
+    let used: HashSet<&str> = matches.iter().map(|m| m.epg_channel_id.as_str()).collect();
+    let channels_with_guide_data = matches.iter()
+        .filter(|m| by_id.get(m.epg_channel_id.as_str()).is_some_and(|c| c.programme_count > 0))
+        .count();
+    let unused_guide_channels = guide.channels.iter()
+        .filter(|c| !used.contains(c.id.as_str()))
+        .map(|c| c.id.clone())
+        .collect();
+    missing_tvg_ids.sort();
+    missing_tvg_ids.dedup();
+
+    log::info!(
+        "EPG match: {}/{} channels matched, {} with guide data, {} tvg-ids missing from guide",
+        matches.len(),
+        channels.len(),
+        channels_with_guide_data,
+        missing_tvg_ids.len()
+    );
+
+    EpgMatchReport {
+        matches,
+        total_channels: channels.len(),
+        channels_with_guide_data,
+        missing_tvg_ids,
+        unused_guide_channels,
+    }
+}
@@ -0,0 +1,104 @@
+// ========================================
+// Command Errors
+// ========================================
+
+use serde::{Deserialize, Serialize};
+
+/// An external program a command depends on
+pub struct Dependency {
+    pub name: &'static str,
+    pub install_url: &'static str,
+    pub message: &'static str,
+}
+
+pub const FFMPEG: Dependency = Dependency {
+    name: "ffmpeg",
+    install_url: "https://ffmpeg.org/download.html",
+    message: "FFmpeg is not installed. Please install FFmpeg from https://ffmpeg.org/download.html and make sure it is on your PATH",
+};
+
+pub const WIREGUARD: Dependency = Dependency {
+    name: "wireguard",
+    install_url: "https://www.wireguard.com/install/",
+    message: "WireGuard is not installed. Please install WireGuard from https://www.wireguard.com/install/",
+};
+
+#[cfg(windows)]
+pub const OPENVPN: Dependency = Dependency {
+    name: "openvpn",
+    install_url: "https://openvpn.net/community-downloads/",
+    message: "OpenVPN is not installed. Please install OpenVPN from https://openvpn.net/community-downloads/",
+};
+
+#[cfg(unix)]
+pub const OPENVPN: Dependency = Dependency {
+    name: "openvpn",
+    install_url: "https://community.openvpn.net/openvpn/wiki/OpenvpnSoftwareRepos",
+    message: "OpenVPN is not installed. Please install it with your package manager (e.g. 'apt install openvpn' or 'brew install openvpn')",
+};
+
+/// Error of commands that run external programs, tagged by `kind` so the UI can offer
+/// an install link for a missing dependency. Both variants carry a readable `message`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum CommandError {
+    BinaryMissing {
+        name: String,
+        install_url: String,
+        message: String,
+    },
+    /// The server's certificate isn't the one pinned for the host
+    CertPinMismatch {
+        host: String,
+        expected: String,
+        /// SHA-256 of the certificate that was presented
+        actual: String,
+        message: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl CommandError {
+    pub fn missing(dependency: &Dependency) -> Self {
+        CommandError::BinaryMissing {
+            name: dependency.name.to_string(),
+            install_url: dependency.install_url.to_string(),
+            message: dependency.message.to_string(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CommandError::BinaryMissing { message, .. }
+            | CommandError::CertPinMismatch { message, .. }
+            | CommandError::Failed { message } => message,
+        }
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed { message }
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Failed { message: message.to_string() }
+    }
+}
+
+/// Lets helpers that report plain strings use `?` on a `CommandError`
+impl From<CommandError> for String {
+    fn from(error: CommandError) -> Self {
+        error.to_string()
+    }
+}
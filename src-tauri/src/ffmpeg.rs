@@ -0,0 +1,150 @@
+// ========================================
+// FFmpeg helpers
+// ========================================
+
+use crate::errors::{self, CommandError};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// Hardware encoders we know how to drive, in order of preference
+pub const HW_ENCODERS: &[&str] = &["h264_nvenc", "h264_qsv", "h264_videotoolbox"];
+
+/// Software encoder used when no (working) accelerator is available
+pub const SOFTWARE_ENCODER: &str = "libx264";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncoderInfo {
+    pub name: String,
+    /// Encoder is compiled into this FFmpeg build
+    pub compiled: bool,
+    /// A short test encode succeeded, i.e. the hardware/driver is actually present
+    pub usable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HwAccelInfo {
+    /// Methods reported by `ffmpeg -hwaccels`
+    pub hwaccels: Vec<String>,
+    pub encoders: Vec<EncoderInfo>,
+}
+
+/// Locate the FFmpeg binary on PATH
+pub fn find_ffmpeg() -> Option<String> {
+    let binary = if cfg!(windows) { "ffmpeg.exe" } else { "ffmpeg" };
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+pub fn require_ffmpeg() -> Result<String, CommandError> {
+    find_ffmpeg().ok_or_else(|| CommandError::missing(&errors::FFMPEG))
+}
+
+async fn ffmpeg_stdout(ffmpeg: &str, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new(ffmpeg);
+    command.args(args);
+    #[cfg(windows)]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = command
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Major version from `ffmpeg -version` ("ffmpeg version 4.4.2-0ubuntu0.22.04.1" or
+/// "n6.1"); `None` for git builds ("N-113000-g...") and unreadable output
+pub async fn major_version(ffmpeg: &str) -> Option<u32> {
+    let stdout = ffmpeg_stdout(ffmpeg, &["-version"]).await.ok()?;
+    let version = stdout.lines().next()?.strip_prefix("ffmpeg version ")?;
+    let digits: String = version.trim_start_matches('n').chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// RTSP socket timeout option. Before FFmpeg 5.0 `-timeout` was the listen timeout and
+/// switched the input into listen mode, the socket timeout was `-stimeout`.
+pub async fn rtsp_timeout_option(ffmpeg: &str) -> &'static str {
+    match major_version(ffmpeg).await {
+        Some(major) if major < 5 => "-stimeout",
+        // Git builds are recent
+        _ => "-timeout",
+    }
+}
+
+/// Encoders compiled into this FFmpeg build (`ffmpeg -encoders`)
+async fn compiled_encoders(ffmpeg: &str) -> Result<Vec<String>, String> {
+    let stdout = ffmpeg_stdout(ffmpeg, &["-hide_banner", "-encoders"]).await?;
+    // Lines look like " V....D h264_nvenc           NVIDIA NVENC H.264 encoder"
+    Ok(stdout.lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|s| s.to_string())
+        .collect())
+}
+
+/// Run a tiny encode to check the encoder works on this machine
+async fn probe_encoder(ffmpeg: &str, encoder: &str) -> bool {
+    let mut command = Command::new(ffmpeg);
+    command
+        .args([
+            "-hide_banner", "-loglevel", "error",
+            "-f", "lavfi", "-i", "color=black:s=256x256:d=0.1",
+            "-c:v", encoder,
+            "-f", "null", "-",
+        ])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    #[cfg(windows)]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let status = command.status().await;
+    matches!(status, Ok(s) if s.success())
+}
+
+/// Pick the encoder to use for `requested`, falling back to libx264 if it can't run here.
+/// Returns the encoder name and whether a fallback happened.
+pub async fn resolve_video_encoder(ffmpeg: &str, requested: &str) -> (String, bool) {
+    if requested == SOFTWARE_ENCODER {
+        return (requested.to_string(), false);
+    }
+
+    let compiled = compiled_encoders(ffmpeg).await.unwrap_or_default();
+    if compiled.iter().any(|e| e == requested) && probe_encoder(ffmpeg, requested).await {
+        (requested.to_string(), false)
+    } else {
+        log::warn!("Encoder '{}' is not available, falling back to {}", requested, SOFTWARE_ENCODER);
+        (SOFTWARE_ENCODER.to_string(), true)
+    }
+}
+
+/// List hardware acceleration methods and which H.264 hardware encoders are usable
+#[tauri::command]
+pub async fn list_hwaccels() -> Result<HwAccelInfo, CommandError> {
+    let ffmpeg = require_ffmpeg()?;
+    log::info!("Probing FFmpeg hardware acceleration...");
+
+    // Output is a header line followed by one method per line
+    let stdout = ffmpeg_stdout(&ffmpeg, &["-hide_banner", "-hwaccels"]).await?;
+    let hwaccels = stdout.lines()
+        .skip_while(|line| !line.starts_with("Hardware acceleration methods"))
+        .skip(1)
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let compiled = compiled_encoders(&ffmpeg).await?;
+    let mut encoders = Vec::new();
+    for name in HW_ENCODERS {
+        let is_compiled = compiled.iter().any(|e| e == name);
+        let usable = is_compiled && probe_encoder(&ffmpeg, name).await;
+        encoders.push(EncoderInfo {
+            name: name.to_string(),
+            compiled: is_compiled,
+            usable,
+        });
+    }
+
+    log::info!("Hardware acceleration: {:?}, encoders: {:?}", hwaccels, encoders);
+
+    Ok(HwAccelInfo { hwaccels, encoders })
+}
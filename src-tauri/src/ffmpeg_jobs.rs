@@ -0,0 +1,286 @@
+// ========================================
+// Custom FFmpeg Jobs
+// ========================================
+
+use crate::console::{self, ConsoleLevel};
+use crate::errors::CommandError;
+use crate::ffmpeg;
+use crate::sessions::ActiveSessions;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+
+/// Options a custom pipeline may use, and whether each takes a value.
+/// Anything else (notably `-f`, `-y`, output paths and `-dump_attachment`) is rejected.
+const ALLOWED_OPTIONS: &[(&str, bool)] = &[
+    ("-i", true), ("-re", false), ("-ss", true), ("-t", true), ("-to", true),
+    ("-stream_loop", true), ("-user_agent", true), ("-headers", true), ("-rtsp_transport", true),
+    ("-hwaccel", true), ("-threads", true), ("-map", true), ("-shortest", false),
+    ("-c", true), ("-c:v", true), ("-c:a", true), ("-c:s", true), ("-codec", true),
+    ("-codec:v", true), ("-codec:a", true), ("-vcodec", true), ("-acodec", true),
+    ("-an", false), ("-vn", false), ("-sn", false),
+    ("-b:v", true), ("-b:a", true), ("-maxrate", true), ("-bufsize", true), ("-crf", true),
+    ("-preset", true), ("-tune", true), ("-profile:v", true), ("-level", true),
+    ("-g", true), ("-r", true), ("-s", true), ("-aspect", true), ("-pix_fmt", true),
+    ("-ar", true), ("-ac", true), ("-frames:v", true), ("-vframes", true),
+    ("-vf", true), ("-af", true), ("-filter:v", true), ("-filter:a", true), ("-filter_complex", true),
+    ("-movflags", true),
+];
+
+/// Input protocols a custom pipeline may read from; `file:`, `concat:`, `pipe:` etc. are out
+const ALLOWED_INPUT_SCHEMES: &[&str] = &["http", "https", "rtsp", "rtsps", "rtmp", "rtmps", "srt", "udp", "rtp"];
+
+/// Filters a custom graph may use. None of them take a file or socket option; ones that
+/// do (`movie`, `subtitles`, `drawtext`, `psnr`'s `stats_file`, `libvmaf`, ...) are left out.
+const ALLOWED_FILTERS: &[&str] = &[
+    "scale", "fps", "crop", "pad", "format", "setsar", "setdar", "setpts", "trim",
+    "transpose", "hflip", "vflip", "yadif", "bwdif", "fade", "select", "split",
+    "overlay", "hstack", "vstack", "concat", "null", "hwupload", "hwdownload", "eq",
+    "unsharp", "hqdn3d", "cropdetect", "blackdetect", "idet", "showinfo",
+    "volume", "aresample", "aformat", "asetpts", "atrim", "atempo", "afade", "aselect",
+    "asplit", "amix", "amerge", "pan", "loudnorm", "anull", "volumedetect", "silencedetect",
+];
+
+/// Sequence number appended to ids
+static NEXT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Where the job writes its result; paths always land in the app's sandbox directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FfmpegOutput {
+    /// Plain file name (no directories) inside the sandbox directory
+    File(String),
+    /// Discard output, e.g. for analysis filters
+    Null,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FfmpegJobInfo {
+    pub session_id: String,
+    pub output_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FfmpegProgressEvent {
+    session_id: String,
+    /// Raw `-progress` fields such as out_time_ms, frame, speed
+    fields: std::collections::HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FfmpegLogEvent {
+    session_id: String,
+    line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FfmpegExitEvent {
+    session_id: String,
+    code: Option<i32>,
+}
+
+pub struct FfmpegJob {
+    child: Child,
+}
+
+/// Check one filter of a graph, e.g. `[0:v]scale=1280:-2[out]`: the name must be
+/// allowlisted and no option may contain a path separator
+fn check_filter(filter: &str) -> Result<(), String> {
+    let invalid = || format!("Invalid filter '{}'", filter.trim());
+    let mut rest = filter.trim();
+    while let Some(after) = rest.strip_prefix('[') {
+        rest = after.split_once(']').ok_or_else(invalid)?.1.trim_start();
+    }
+    while rest.ends_with(']') {
+        rest = rest.rsplit_once('[').ok_or_else(invalid)?.0.trim_end();
+    }
+
+    let (name, options) = rest.split_once('=').unwrap_or((rest, ""));
+    // `scale@main` names an instance of `scale`
+    let name = name.split('@').next().unwrap_or_default().trim();
+    if !ALLOWED_FILTERS.contains(&name) {
+        return Err(format!("Filter '{}' is not allowed", name));
+    }
+    if options.contains(['/', '\\']) {
+        return Err(format!("Filter '{}' has an option with a path separator: '{}'", name, options));
+    }
+    Ok(())
+}
+
+/// Check `args` against the allowlist
+fn validate_args(args: &[String]) -> Result<(), String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let Some(&(option, takes_value)) = ALLOWED_OPTIONS.iter().find(|(name, _)| *name == arg) else {
+            return Err(if arg.starts_with('-') {
+                format!("FFmpeg option '{}' is not allowed", arg)
+            } else {
+                format!("Unexpected argument '{}': outputs are chosen with output_kind", arg)
+            });
+        };
+        if !takes_value {
+            continue;
+        }
+        let value = iter.next().ok_or_else(|| format!("{} needs a value", option))?;
+
+        match option {
+            "-i" => {
+                let scheme = value.split_once("://").map(|(scheme, _)| scheme.to_lowercase());
+                if !scheme.is_some_and(|s| ALLOWED_INPUT_SCHEMES.contains(&s.as_str())) {
+                    return Err(format!("Input '{}' is not allowed, use a network URL", value));
+                }
+            }
+            "-vf" | "-af" | "-filter:v" | "-filter:a" | "-filter_complex" => {
+                // Quoted or escaped separators split into pieces that fail the check, never pass it
+                for filter in value.split([';', ',']).filter(|f| !f.trim().is_empty()) {
+                    check_filter(filter)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !args.iter().any(|arg| arg == "-i") {
+        return Err("At least one -i input is required".to_string());
+    }
+    Ok(())
+}
+
+/// Run a custom FFmpeg pipeline built from allowlisted `args`. The output is chosen by
+/// `output_kind` and confined to the app's sandbox directory. Progress and stderr are
+/// streamed as `ffmpeg://progress` / `ffmpeg://log` events, and `ffmpeg://exit` fires at the end.
+#[tauri::command]
+pub async fn run_ffmpeg(
+    app: AppHandle,
+    sessions: State<'_, ActiveSessions>,
+    args: Vec<String>,
+    output_kind: FfmpegOutput,
+) -> Result<FfmpegJobInfo, CommandError> {
+    validate_args(&args)?;
+    let ffmpeg_path = ffmpeg::require_ffmpeg()?;
+
+    let (output_args, output_path) = match &output_kind {
+        FfmpegOutput::File(name) => {
+            if !crate::stream_proxy::is_safe_file_name(name) {
+                return Err(format!("Invalid output file name '{}'", name).into());
+            }
+            let dir = app.path().app_data_dir()
+                .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+                .join("ffmpeg-output");
+            tokio::fs::create_dir_all(&dir).await
+                .map_err(|e| format!("Failed to create output directory: {}", e))?;
+            let path = dir.join(name).to_string_lossy().to_string();
+            (vec!["-y".to_string(), path.clone()], Some(path))
+        }
+        FfmpegOutput::Null => (vec!["-f".to_string(), "null".to_string(), "-".to_string()], None),
+    };
+
+    let mut full_args: Vec<String> = vec![
+        "-hide_banner".into(), "-nostdin".into(), "-nostats".into(),
+        "-progress".into(), "pipe:1".into(),
+    ];
+    full_args.extend(args);
+    full_args.extend(output_args);
+
+    log::info!("Executing custom FFmpeg job: {} {}", ffmpeg_path, full_args.join(" "));
+
+    let mut command = Command::new(&ffmpeg_path);
+    command
+        .args(&full_args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start FFmpeg: {}", e))?;
+
+    // The sequence number keeps ids of jobs started in the same millisecond apart
+    let session_id = format!(
+        "ffmpeg-{}-{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default(),
+        NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    );
+
+    // Track the job before the readers start so a quick exit can't miss it
+    let stderr = child.stderr.take();
+    let stdout = child.stdout.take();
+    sessions.ffmpeg_jobs.lock().await.insert(session_id.clone(), FfmpegJob { child });
+
+    if let Some(stderr) = stderr {
+        let app = app.clone();
+        let session_id = session_id.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.to_lowercase().contains("error") {
+                    console::record(ConsoleLevel::Error, "ffmpeg", format!("[{}] {}", session_id, line));
+                }
+                let _ = app.emit("ffmpeg://log", FfmpegLogEvent {
+                    session_id: session_id.clone(),
+                    line,
+                });
+            }
+        });
+    }
+
+    // `-progress` writes key=value blocks ending in progress=continue|end
+    if let Some(stdout) = stdout {
+        let app = app.clone();
+        let session_id = session_id.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut fields = std::collections::HashMap::new();
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                let is_end_of_block = key == "progress";
+                fields.insert(key.trim().to_string(), value.trim().to_string());
+                if is_end_of_block {
+                    let _ = app.emit("ffmpeg://progress", FfmpegProgressEvent {
+                        session_id: session_id.clone(),
+                        fields: std::mem::take(&mut fields),
+                    });
+                }
+            }
+
+            // Output closed: FFmpeg is done unless stop_ffmpeg already took the job
+            let job = app.state::<ActiveSessions>().ffmpeg_jobs.lock().await.remove(&session_id);
+            if let Some(mut job) = job {
+                let code = job.child.wait().await.ok().and_then(|status| status.code());
+                log::info!("FFmpeg job {} exited with {:?}", session_id, code);
+                let _ = app.emit("ffmpeg://exit", FfmpegExitEvent { session_id, code });
+            }
+        });
+    }
+
+    Ok(FfmpegJobInfo {
+        session_id,
+        output_path,
+    })
+}
+
+/// Stop a job started with `run_ffmpeg`. Returns false if it already finished.
+#[tauri::command]
+pub async fn stop_ffmpeg(
+    app: AppHandle,
+    sessions: State<'_, ActiveSessions>,
+    session_id: String,
+) -> Result<bool, String> {
+    let Some(mut job) = sessions.ffmpeg_jobs.lock().await.remove(&session_id) else {
+        return Ok(false);
+    };
+    log::info!("Stopping FFmpeg job {}", session_id);
+    job.child.kill().await
+        .map_err(|e| format!("Failed to stop FFmpeg: {}", e))?;
+    let code = job.child.wait().await.ok().and_then(|status| status.code());
+    let _ = app.emit("ffmpeg://exit", FfmpegExitEvent { session_id, code });
+    Ok(true)
+}
@@ -0,0 +1,162 @@
+// ========================================
+// HLS Master Playlists
+// ========================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A `#EXT-X-STREAM-INF` variant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HlsVariant {
+    pub uri: String,
+    pub bandwidth: Option<u64>,
+    pub average_bandwidth: Option<u64>,
+    /// `RESOLUTION` as written, e.g. "1920x1080"
+    pub resolution: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f32>,
+    pub codecs: Option<String>,
+    /// `GROUP-ID`s of the renditions this variant plays with
+    pub audio_group: Option<String>,
+    pub subtitles_group: Option<String>,
+    pub video_group: Option<String>,
+    pub closed_captions_group: Option<String>,
+}
+
+/// A `#EXT-X-MEDIA` rendition (alternative audio, subtitles, video or captions)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HlsRendition {
+    /// AUDIO, SUBTITLES, VIDEO or CLOSED-CAPTIONS
+    pub media_type: String,
+    pub group_id: String,
+    pub name: Option<String>,
+    pub language: Option<String>,
+    pub default: bool,
+    pub autoselect: bool,
+    pub forced: bool,
+    /// Audio channel count and layout, e.g. "6"
+    pub channels: Option<String>,
+    /// Closed captions have no URI, they are carried in the video
+    pub instream_id: Option<String>,
+    /// `None` when the rendition is muxed into the variant stream
+    pub uri: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HlsMaster {
+    /// False for a media playlist (segments only), which has no variants or renditions
+    pub is_master: bool,
+    pub variants: Vec<HlsVariant>,
+    pub renditions: Vec<HlsRendition>,
+    /// Groups referenced by a variant that no `#EXT-X-MEDIA` defines
+    pub missing_groups: Vec<String>,
+}
+
+/// Split an attribute list (`KEY=value,KEY="quoted, value"`) into its attributes
+fn parse_attributes(list: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = list.trim();
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_uppercase();
+        let (value, remaining) = match after.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, remaining)) => (value, remaining),
+                None => (quoted, ""),
+            },
+            None => after.split_once(',').map_or((after, ""), |(value, remaining)| (value, remaining)),
+        };
+        attributes.insert(key, value.trim().to_string());
+        rest = remaining.trim_start_matches(',');
+    }
+    attributes
+}
+
+/// Parse a master playlist's variants and renditions, resolving URIs against `base_url`
+#[tauri::command]
+pub fn parse_hls_master(manifest: String, base_url: String) -> Result<HlsMaster, String> {
+    let base = url::Url::parse(&base_url).map_err(|e| format!("Invalid base URL: {}", e))?;
+    if !manifest.trim_start_matches('\u{feff}').trim_start().starts_with("#EXTM3U") {
+        return Err("Not an HLS playlist (missing #EXTM3U)".to_string());
+    }
+    let resolve = |uri: &str| base.join(uri.trim()).map(|u| u.to_string()).unwrap_or_else(|_| uri.trim().to_string());
+
+    let mut variants = Vec::new();
+    let mut renditions = Vec::new();
+    // The variant URI is the next non-tag line after #EXT-X-STREAM-INF
+    let mut pending: Option<HashMap<String, String>> = None;
+
+    for line in manifest.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(list) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            pending = Some(parse_attributes(list));
+        } else if let Some(list) = line.strip_prefix("#EXT-X-MEDIA:") {
+            let mut attrs = parse_attributes(list);
+            let flag = |attrs: &HashMap<String, String>, key: &str| attrs.get(key).is_some_and(|v| v.eq_ignore_ascii_case("YES"));
+            let (default, autoselect, forced) = (flag(&attrs, "DEFAULT"), flag(&attrs, "AUTOSELECT"), flag(&attrs, "FORCED"));
+            renditions.push(HlsRendition {
+                media_type: attrs.remove("TYPE").unwrap_or_default().to_uppercase(),
+                group_id: attrs.remove("GROUP-ID").unwrap_or_default(),
+                name: attrs.remove("NAME"),
+                language: attrs.remove("LANGUAGE"),
+                default,
+                autoselect,
+                forced,
+                channels: attrs.remove("CHANNELS"),
+                instream_id: attrs.remove("INSTREAM-ID"),
+                uri: attrs.remove("URI").map(|uri| resolve(&uri)),
+            });
+        } else if !line.starts_with('#') {
+            let Some(mut attrs) = pending.take() else {
+                continue;
+            };
+            let resolution = attrs.remove("RESOLUTION");
+            let (width, height) = resolution.as_deref()
+                .and_then(|r| r.split_once(['x', 'X']))
+                .map_or((None, None), |(w, h)| (w.parse().ok(), h.parse().ok()));
+            // CLOSED-CAPTIONS=NONE means the variant has none, not a group called NONE
+            let closed_captions_group = attrs.remove("CLOSED-CAPTIONS").filter(|group| group != "NONE");
+            variants.push(HlsVariant {
+                uri: resolve(line),
+                bandwidth: attrs.get("BANDWIDTH").and_then(|v| v.parse().ok()),
+                average_bandwidth: attrs.get("AVERAGE-BANDWIDTH").and_then(|v| v.parse().ok()),
+                resolution,
+                width,
+                height,
+                frame_rate: attrs.get("FRAME-RATE").and_then(|v| v.parse().ok()),
+                codecs: attrs.remove("CODECS"),
+                audio_group: attrs.remove("AUDIO"),
+                subtitles_group: attrs.remove("SUBTITLES"),
+                video_group: attrs.remove("VIDEO"),
+                closed_captions_group,
+            });
+        }
+    }
+
+    let mut missing_groups: Vec<String> = Vec::new();
+    for variant in &variants {
+        let references = [
+            ("AUDIO", &variant.audio_group),
+            ("SUBTITLES", &variant.subtitles_group),
+            ("VIDEO", &variant.video_group),
+            ("CLOSED-CAPTIONS", &variant.closed_captions_group),
+        ];
+        for (media_type, group) in references {
+            let Some(group) = group else {
+                continue;
+            };
+            let defined = renditions.iter().any(|r| r.media_type == media_type && &r.group_id == group);
+            let label = format!("{}:{}", media_type, group);
+            if !defined && !missing_groups.contains(&label) {
+                missing_groups.push(label);
+            }
+        }
+    }
+
+    log::info!("Parsed HLS master: {} variants, {} renditions", variants.len(), renditions.len());
+    Ok(HlsMaster {
+        is_master: !variants.is_empty() || !renditions.is_empty(),
+        variants,
+        renditions,
+        missing_groups,
+    })
+}
@@ -0,0 +1,187 @@
+// ========================================
+// Shared HTTP Client and System Proxy
+// ========================================
+
+use crate::cert_pin::CertPin;
+use crate::dns::VpnDnsResolver;
+use crate::settings::{AppSettings, HeaderRule, SettingsStore};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use tauri::State;
+
+/// Client used by `http_fetch`, rebuilt whenever a setting it depends on changes
+pub struct SharedHttpClient(RwLock<reqwest::Client>);
+
+/// Base client configuration derived from the app settings
+pub fn client_builder(settings: &AppSettings) -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30));
+    // reqwest honors HTTP(S)_PROXY and the OS proxy unless told otherwise
+    if settings.respect_system_proxy {
+        builder
+    } else {
+        builder.no_proxy()
+    }
+}
+
+/// Dedicated client for `http_fetch` options the shared client can't serve: a pinned
+/// HTTP version ("http1"/"http2"), resolution through the VPN's DNS servers or a
+/// pinned certificate. Returns `None` when the shared client fits.
+pub fn client_for_request(
+    settings: &AppSettings,
+    http_version: &str,
+    vpn_dns: Option<Vec<IpAddr>>,
+    cert_pin: Option<&CertPin>,
+) -> Result<Option<reqwest::Client>, String> {
+    let http_version = http_version.to_lowercase();
+    let mut builder = match http_version.as_str() {
+        "auto" if vpn_dns.is_none() && cert_pin.is_none() => return Ok(None),
+        "auto" => client_builder(settings),
+        "http1" => client_builder(settings).http1_only(),
+        "http2" => client_builder(settings).http2_prior_knowledge(),
+        other => return Err(format!("Unsupported HTTP version: {} (expected auto, http1 or http2)", other)),
+    };
+    if let Some(servers) = vpn_dns {
+        builder = builder.dns_resolver(Arc::new(VpnDnsResolver::new(servers)));
+    }
+    if let Some(pin) = cert_pin {
+        builder = builder.use_preconfigured_tls(pin.tls_config(&http_version)?);
+    }
+    builder.build()
+        .map(Some)
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+impl SharedHttpClient {
+    pub fn new(settings: &AppSettings) -> Result<Self, String> {
+        let client = client_builder(settings)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(Self(RwLock::new(client)))
+    }
+
+    /// Cheap handle to the current client (reqwest clients share their pool)
+    pub fn get(&self) -> reqwest::Client {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn rebuild(&self, settings: &AppSettings) -> Result<(), String> {
+        let client = client_builder(settings)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = client;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SystemProxyInfo {
+    pub http_proxy: Option<String>,
+    pub https_proxy: Option<String>,
+    pub all_proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    /// OS-level proxy configuration (WinHTTP/WinINet on Windows, scutil on macOS)
+    pub os_proxy: Option<String>,
+    /// Whether `http_fetch` currently uses these settings
+    pub respect_system_proxy: bool,
+}
+
+/// Proxy environment variables come in upper and lower case
+fn proxy_env(name: &str) -> Option<String> {
+    std::env::var(name.to_uppercase())
+        .or_else(|_| std::env::var(name.to_lowercase()))
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+}
+
+async fn os_proxy() -> Option<String> {
+    let run = |program: &'static str, args: &'static [&'static str]| async move {
+        let output = tokio::process::Command::new(program).args(args).output().await.ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    if cfg!(windows) {
+        let winhttp = run("netsh", &["winhttp", "show", "proxy"]).await;
+        let wininet = run("reg", &[
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Internet Settings",
+            "/v", "ProxyServer",
+        ]).await;
+        let parts: Vec<String> = [winhttp, wininet].into_iter().flatten().collect();
+        (!parts.is_empty()).then(|| parts.join("\n"))
+    } else if cfg!(target_os = "macos") {
+        run("scutil", &["--proxy"]).await
+    } else {
+        None
+    }
+}
+
+/// Read the proxy configuration the app would inherit from the OS
+#[tauri::command]
+pub async fn get_system_proxy(settings: State<'_, SettingsStore>) -> Result<SystemProxyInfo, String> {
+    Ok(SystemProxyInfo {
+        http_proxy: proxy_env("http_proxy"),
+        https_proxy: proxy_env("https_proxy"),
+        all_proxy: proxy_env("all_proxy"),
+        no_proxy: proxy_env("no_proxy"),
+        os_proxy: os_proxy().await,
+        respect_system_proxy: settings.get().respect_system_proxy,
+    })
+}
+
+/// Choose whether `http_fetch` goes through the system proxy; persisted in settings
+#[tauri::command]
+pub fn set_respect_system_proxy(
+    settings: State<'_, SettingsStore>,
+    client: State<'_, SharedHttpClient>,
+    respect: bool,
+) -> Result<(), String> {
+    log::info!("Setting respect_system_proxy = {}", respect);
+    let updated = settings.update(|s| s.respect_system_proxy = respect)?;
+    client.rebuild(&updated)
+}
+
+/// Headers from the global rules for `url`, minus any the request sets itself
+/// (compared case-insensitively), so per-request headers take precedence
+pub fn global_headers(rules: &[HeaderRule], url: &str, request_headers: &[(String, String)]) -> Vec<(String, String)> {
+    let Some(host) = url::Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_string())) else {
+        return Vec::new();
+    };
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for (name, value) in rules.iter().filter(|rule| rule.matches(&host)).flat_map(|rule| &rule.headers) {
+        if request_headers.iter().any(|(own, _)| own.eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        headers.push((name.clone(), value.clone()));
+    }
+    headers
+}
+
+/// Global header rules applied to every `http_fetch`
+#[tauri::command]
+pub fn get_global_headers(settings: State<'_, SettingsStore>) -> Vec<HeaderRule> {
+    settings.get().header_rules
+}
+
+/// Replace the global header rules; persisted in settings
+#[tauri::command]
+pub fn set_global_headers(settings: State<'_, SettingsStore>, rules: Vec<HeaderRule>) -> Result<(), String> {
+    for rule in &rules {
+        let pattern = rule.host_pattern.trim();
+        let domain = pattern.strip_prefix("*.").unwrap_or(pattern);
+        if pattern.is_empty() || (pattern != "*" && (domain.is_empty() || domain.contains(['*', '/', ':', ' ']))) {
+            return Err(format!("Invalid host pattern '{}': use a host, *.domain or *", rule.host_pattern));
+        }
+        for (name, value) in &rule.headers {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("Invalid header name '{}'", name))?;
+            reqwest::header::HeaderValue::from_str(value)
+                .map_err(|_| format!("Invalid value for header '{}'", name))?;
+        }
+    }
+    log::info!("Setting {} global header rules", rules.len());
+    settings.update(|s| s.header_rules = rules)?;
+    Ok(())
+}
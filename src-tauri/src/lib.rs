@@ -2,14 +2,71 @@
 use serde::{Deserialize, Serialize};
 use base64::{Engine as _, engine::general_purpose};
 use std::process::Command;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio_util::sync::CancellationToken;
+use console::ConsoleLevel;
+use errors::CommandError;
+
+mod backup;
+mod cert_pin;
+mod console;
+mod diagnostics;
+mod dns;
+mod download;
+mod epg;
+mod errors;
+mod ffmpeg;
+mod ffmpeg_jobs;
+mod hls;
+mod http_client;
+mod local_tls;
+mod logos;
+mod playlist;
+mod private_files;
+mod recording;
+mod routing;
+mod secrets;
+mod sessions;
+mod settings;
+mod sniff;
+mod stream_proxy;
+mod temp_dirs;
+mod throughput;
+mod urls;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HttpResponse {
     body: String,
     status: u16,
     headers: std::collections::HashMap<String, String>,
+    /// Protocol the response arrived over, e.g. "HTTP/1.1" or "HTTP/2.0"
+    http_version: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     is_binary: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timing: Option<ResponseTiming>,
+    /// Delay requested by a 429/503 `Retry-After` header, after capping
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_ms: Option<u64>,
+    /// The body arrived gzip-compressed without a usable Content-Encoding and was decoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gzip_decoded: Option<bool>,
+    /// For a request with a `Range` header: true for a 206 partial body, false when the
+    /// full resource came back (the `If-Range` validator no longer matches or the
+    /// server ignores ranges) and earlier bytes must be discarded, not appended to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range_applied: Option<bool>,
+}
+
+/// Coarse timing breakdown of an `http_fetch` request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseTiming {
+    /// Time until the response headers arrived (includes DNS, connect and TLS)
+    first_byte_ms: u64,
+    /// Time spent reading the response body
+    body_ms: u64,
+    /// Total time from sending the request to having the full body
+    total_ms: u64,
 }
 
 // ========================================
@@ -41,6 +98,12 @@ pub struct VpnConfigInfo {
     pub endpoint: Option<String>,
     pub dns: Option<String>,
     pub address: Option<String>,
+    /// Ranges routed into the tunnel (WireGuard `AllowedIPs`, all peers)
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    /// Whether a `[Peer]` carries a `PresharedKey`
+    #[serde(default)]
+    pub has_preshared_key: bool,
     pub is_valid: bool,
     pub error: Option<String>,
 }
@@ -54,14 +117,93 @@ pub enum VpnStatus {
     Error,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WireGuardServiceInfo {
+    pub tunnel_name: String,
+    pub running: bool,
+    pub removed: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TunnelCleanupSummary {
+    /// Every WireGuard tunnel service found, whether or not it matched the prefix
+    pub tunnels: Vec<WireGuardServiceInfo>,
+    pub removed_count: usize,
+}
+
+/// Outcome of the elevation pre-flight
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ElevationStatus {
+    /// The no-op ran with admin rights after a prompt
+    Available,
+    /// The app already runs elevated, connects won't prompt
+    AlreadyElevated,
+    /// The user dismissed the prompt
+    Cancelled,
+    /// Policy denies elevation for this account
+    BlockedByPolicy,
+    /// No usable elevation mechanism (e.g. sudo needs a password and there's no pkexec)
+    Unavailable,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ElevationCheck {
+    pub status: ElevationStatus,
+    pub detail: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VpnStatusInfo {
     pub status: VpnStatus,
     pub vpn_type: Option<VpnType>,
     pub tunnel_name: Option<String>,
+    /// Addresses the tunnel interface holds, v4 and v6 comma separated
+    #[serde(default)]
+    pub assigned_ip: Option<String>,
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WireGuardPeer {
+    pub public_key: String,
+    pub endpoint: Option<String>,
+    pub allowed_ips: Vec<String>,
+    /// Unix time of the latest handshake, `None` if there hasn't been one
+    pub latest_handshake: Option<u64>,
+    pub handshake_age_secs: Option<u64>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub persistent_keepalive: Option<u32>,
+    /// Handshake recent enough that the session keys are still valid
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WireGuardPeersInfo {
+    pub tunnel_name: String,
+    /// Connected if any peer is active, Connecting while none has a fresh handshake
+    pub status: VpnStatus,
+    pub public_key: Option<String>,
+    pub listen_port: Option<u16>,
+    pub peers: Vec<WireGuardPeer>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenVpnStats {
+    pub status: VpnStatus,
+    /// Encrypted traffic on the wire (TCP/UDP read/write bytes)
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// Plain traffic through the tunnel device (TUN/TAP read/write bytes)
+    pub tun_read_bytes: u64,
+    pub tun_write_bytes: u64,
+    /// Time since this app connected the tunnel
+    pub uptime_secs: Option<u64>,
+    /// Time since the status file was last rewritten
+    pub status_age_secs: Option<u64>,
+}
+
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! Welcome to ClaudeTV!", name)
@@ -71,6 +213,18 @@ fn greet(name: &str) -> String {
 // VPN Detection Commands
 // ========================================
 
+/// Where connect/disconnect expect the WireGuard for Windows client
+const WIREGUARD_EXE: &str = r"C:\Program Files\WireGuard\wireguard.exe";
+
+/// WireGuard's CLI, used to read live peer state
+#[cfg(windows)]
+const WG_EXE: &str = r"C:\Program Files\WireGuard\wg.exe";
+#[cfg(unix)]
+const WG_EXE: &str = "wg";
+
+#[cfg(windows)]
+const OPENVPN_EXE: &str = r"C:\Program Files\OpenVPN\bin\openvpn.exe";
+
 /// Detect available VPN clients on the system
 #[tauri::command]
 fn detect_vpn_clients() -> DetectedVpnClients {
@@ -87,16 +241,21 @@ fn detect_vpn_clients() -> DetectedVpnClients {
         .map(|s| s.to_string());
     
     // OpenVPN detection (Windows paths)
+    #[cfg(windows)]
     let openvpn_paths = vec![
         r"C:\Program Files\OpenVPN\bin\openvpn.exe",
         r"C:\Program Files (x86)\OpenVPN\bin\openvpn.exe",
         r"C:\Program Files\OpenVPN Connect\ovpncli.exe",
     ];
     
+    #[cfg(windows)]
     let openvpn_path = openvpn_paths.iter()
         .find(|p| std::path::Path::new(p).exists())
         .map(|s| s.to_string());
     
+    #[cfg(unix)]
+    let openvpn_path = find_openvpn_binary();
+    
     log::info!("WireGuard found: {:?}, OpenVPN found: {:?}", wireguard_path, openvpn_path);
     
     DetectedVpnClients {
@@ -132,6 +291,8 @@ fn parse_vpn_config(content: String) -> VpnConfigInfo {
             endpoint: None,
             dns: None,
             address: None,
+            allowed_ips: Vec::new(),
+            has_preshared_key: false,
             is_valid: false,
             error: Some("Unknown VPN configuration format. Expected WireGuard (.conf) or OpenVPN (.ovpn)".to_string()),
         }
@@ -142,53 +303,176 @@ fn parse_wireguard_config(content: &str) -> VpnConfigInfo {
     let mut endpoint = None;
     let mut dns = None;
     let mut address = None;
+    let mut allowed_ips = Vec::new();
     let mut has_private_key = false;
     let mut has_public_key = false;
+    let mut has_preshared_key = false;
+    let mut invalid_keys = Vec::new();
     
     for line in content.lines() {
-        let line = line.trim();
-        if line.starts_with("Endpoint") {
-            endpoint = line.split('=').nth(1).map(|s| s.trim().to_string());
-        } else if line.starts_with("DNS") {
-            dns = line.split('=').nth(1).map(|s| s.trim().to_string());
-        } else if line.starts_with("Address") {
-            address = line.split('=').nth(1).map(|s| s.trim().to_string());
-        } else if line.starts_with("PrivateKey") {
-            has_private_key = true;
-        } else if line.starts_with("PublicKey") {
-            has_public_key = true;
+        let line = line.split('#').next().unwrap_or_default();
+        // Keys are base64 and end in '=', so only split on the first one
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "AllowedIPs" => {
+                allowed_ips.extend(value.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()));
+            }
+            "Endpoint" => endpoint = Some(value.to_string()),
+            "DNS" => dns = Some(value.to_string()),
+            "Address" => address = Some(value.to_string()),
+            key @ ("PrivateKey" | "PublicKey" | "PresharedKey") => {
+                match key {
+                    "PrivateKey" => has_private_key = true,
+                    "PublicKey" => has_public_key = true,
+                    _ => has_preshared_key = true,
+                }
+                if !is_valid_wireguard_key(value) && !invalid_keys.contains(&key) {
+                    invalid_keys.push(key);
+                }
+            }
+            _ => {}
         }
     }
     
-    let is_valid = has_private_key && has_public_key && endpoint.is_some();
-    let error = if !is_valid {
-        let mut missing = Vec::new();
-        if !has_private_key { missing.push("PrivateKey"); }
-        if !has_public_key { missing.push("PublicKey"); }
-        if endpoint.is_none() { missing.push("Endpoint"); }
-        Some(format!("Missing required fields: {}", missing.join(", ")))
-    } else {
-        None
-    };
+    let mut problems = Vec::new();
+    let mut missing = Vec::new();
+    if !has_private_key { missing.push("PrivateKey"); }
+    if !has_public_key { missing.push("PublicKey"); }
+    if endpoint.is_none() { missing.push("Endpoint"); }
+    if !missing.is_empty() {
+        problems.push(format!("Missing required fields: {}", missing.join(", ")));
+    }
+    if !invalid_keys.is_empty() {
+        problems.push(format!("Invalid keys (expected 32-byte base64): {}", invalid_keys.join(", ")));
+    }
+    
+    let is_valid = problems.is_empty();
+    let error = (!is_valid).then(|| problems.join("; "));
     
     VpnConfigInfo {
         vpn_type: VpnType::WireGuard,
         endpoint,
         dns,
         address,
+        allowed_ips,
+        has_preshared_key,
         is_valid,
         error,
     }
 }
 
+/// WireGuard keys are 32 bytes, base64 encoded (44 characters with padding)
+fn is_valid_wireguard_key(value: &str) -> bool {
+    value.len() == 44
+        && general_purpose::STANDARD.decode(value).is_ok_and(|bytes| bytes.len() == 32)
+}
+
+/// Canonical spelling of the WireGuard keys we know about
+const WIREGUARD_KEYS: &[&str] = &[
+    "PrivateKey", "Address", "ListenPort", "DNS", "MTU", "Table", "FwMark", "SaveConfig",
+    "PreUp", "PostUp", "PreDown", "PostDown",
+    "PublicKey", "PresharedKey", "AllowedIPs", "Endpoint", "PersistentKeepalive",
+];
+
+/// Keys holding comma separated lists
+const WIREGUARD_LIST_KEYS: &[&str] = &["Address", "DNS", "AllowedIPs"];
+
+/// Read a WireGuard config as (section, key, value) entries, dropping comments
+/// the way wg-quick does (everything after `#`) and normalizing names and spacing
+fn wireguard_entries(content: &str) -> Result<Vec<(String, String, String)>, String> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+    
+    for (number, raw) in content.lines().enumerate() {
+        let line = raw.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        
+        if line.starts_with('[') && line.ends_with(']') {
+            let name = line[1..line.len() - 1].trim();
+            section = match name.to_lowercase().as_str() {
+                "interface" => "Interface".to_string(),
+                "peer" => "Peer".to_string(),
+                _ => name.to_string(),
+            };
+            entries.push((section.clone(), String::new(), String::new()));
+            continue;
+        }
+        
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("Line {}: expected 'Key = Value', got '{}'", number + 1, line));
+        };
+        let key = key.trim();
+        let key = WIREGUARD_KEYS.iter()
+            .find(|known| known.eq_ignore_ascii_case(key))
+            .map(|known| known.to_string())
+            .unwrap_or_else(|| key.to_string());
+        let value = if WIREGUARD_LIST_KEYS.contains(&key.as_str()) {
+            value.split(',').map(str::trim).filter(|v| !v.is_empty()).collect::<Vec<_>>().join(", ")
+        } else {
+            value.trim().to_string()
+        };
+        entries.push((section.clone(), key, value));
+    }
+    
+    Ok(entries)
+}
+
+/// Normalize a pasted WireGuard config: strip comments and stray whitespace,
+/// canonicalize section headers and `Key = Value` spacing, and use `\n` line endings.
+/// The result is re-parsed to make sure nothing but formatting changed.
+#[tauri::command]
+fn normalize_wireguard_config(content: String) -> Result<String, String> {
+    let entries = wireguard_entries(&content)?;
+    
+    let mut normalized = String::new();
+    for (section, key, value) in &entries {
+        if key.is_empty() {
+            if !normalized.is_empty() {
+                normalized.push('\n');
+            }
+            normalized.push_str(&format!("[{}]\n", section));
+        } else {
+            normalized.push_str(&format!("{} = {}\n", key, value));
+        }
+    }
+    
+    // Same entries after a round trip, and still valid if the original was
+    if wireguard_entries(&normalized)? != entries {
+        return Err("Normalization changed the configuration, keeping the original".to_string());
+    }
+    if parse_wireguard_config(&content).is_valid && !parse_wireguard_config(&normalized).is_valid {
+        return Err("Normalized configuration failed validation, keeping the original".to_string());
+    }
+    
+    Ok(normalized)
+}
+
 fn parse_openvpn_config(content: &str) -> VpnConfigInfo {
     let mut endpoint = None;
     let mut dns = None;
+    let mut allowed_ips = Vec::new();
     let mut has_ca = false;
     
     for line in content.lines() {
         let line = line.trim();
-        if line.starts_with("remote ") {
+        if line.starts_with("redirect-gateway") {
+            allowed_ips.push("0.0.0.0/0".to_string());
+        } else if line.starts_with("route ") {
+            // Format: route network [netmask]
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let network = parts.get(1).and_then(|n| n.parse::<std::net::Ipv4Addr>().ok());
+            let mask = parts.get(2)
+                .and_then(|m| m.parse::<std::net::Ipv4Addr>().ok())
+                .unwrap_or(std::net::Ipv4Addr::BROADCAST);
+            if let Some(network) = network {
+                allowed_ips.push(format!("{}/{}", network, u32::from(mask).count_ones()));
+            }
+        } else if line.starts_with("remote ") {
             // Format: remote hostname port
             let parts: Vec<&str> = line.split_whitespace().collect();
             if parts.len() >= 2 {
@@ -218,51 +502,488 @@ fn parse_openvpn_config(content: &str) -> VpnConfigInfo {
         endpoint,
         dns,
         address: None,
+        allowed_ips,
+        has_preshared_key: false,
         is_valid,
         error,
     }
 }
 
-/// Connect to VPN using the specified config file
+/// Connect to VPN using the specified config file.
+/// If `request_id` is given, the attempt can be aborted with `cancel_connect`.
+/// WireGuard tunnels are named after the config file unless `tunnel_name` is given,
+/// so several tunnels can run side by side.
+///
+/// WireGuard normally installs a tunnel service that starts with Windows and keeps running
+/// after the app closes. With `ephemeral`, the service is set to manual start, so it never
+/// comes back after a reboot, and it is uninstalled when the app exits. If the app crashes,
+/// the tunnel stays up until the next reboot or `cleanup_orphaned_tunnels`.
+#[tauri::command]
+async fn connect_vpn(
+    sessions: State<'_, sessions::ActiveSessions>,
+    config_path: String,
+    vpn_type: VpnType,
+    request_id: Option<String>,
+    tunnel_name: Option<String>,
+    ephemeral: Option<bool>,
+) -> Result<VpnStatusInfo, CommandError> {
+    connect_tunnel(&sessions, config_path, vpn_type, request_id, tunnel_name, ephemeral, None).await
+}
+
+/// Connect from config text instead of a file, e.g. a pasted or imported profile.
+/// The config is validated, written to a file only the current user can read and
+/// deleted again on disconnect (or if the connect fails). `name` names the WireGuard
+/// tunnel; other arguments are as for `connect_vpn`.
 #[tauri::command]
-async fn connect_vpn(config_path: String, vpn_type: VpnType) -> Result<VpnStatusInfo, String> {
+async fn connect_vpn_from_content(
+    sessions: State<'_, sessions::ActiveSessions>,
+    content: String,
+    vpn_type: VpnType,
+    name: Option<String>,
+    request_id: Option<String>,
+    ephemeral: Option<bool>,
+) -> Result<VpnStatusInfo, CommandError> {
+    let info = parse_vpn_config(content.clone());
+    if !info.is_valid {
+        return Err(info.error.unwrap_or_else(|| "Invalid VPN configuration".to_string()).into());
+    }
+    if info.vpn_type != vpn_type {
+        return Err(format!("Config is a {:?} configuration, not {:?}", info.vpn_type, vpn_type).into());
+    }
+
+    let (file_name, tunnel_name) = match vpn_type {
+        VpnType::WireGuard => {
+            let name = name.unwrap_or_else(|| "claudetv_vpn".to_string());
+            validate_tunnel_name(&name)?;
+            (format!("{}.conf", name), Some(name))
+        }
+        VpnType::OpenVPN => ("openvpn.ovpn".to_string(), None),
+    };
+    let config_file = private_files::private_dir(private_files::TUNNEL_CONFIG_DIR)?.join(file_name);
+    private_files::write_private(&config_file, content.as_bytes())?;
+
+    let config_path = config_file.to_string_lossy().to_string();
+    let result = connect_tunnel(&sessions, config_path, vpn_type, request_id, tunnel_name, ephemeral, Some(config_file.clone())).await;
+    let up = result.as_ref().is_ok_and(|info| matches!(info.status, VpnStatus::Connected | VpnStatus::Connecting));
+    if !up {
+        private_files::remove_private(&config_file);
+    }
+    result
+}
+
+/// Shared body of `connect_vpn` and `connect_vpn_from_content`. `temp_config` is a
+/// generated config file to delete when the tunnel is disconnected.
+async fn connect_tunnel(
+    sessions: &sessions::ActiveSessions,
+    config_path: String,
+    vpn_type: VpnType,
+    request_id: Option<String>,
+    tunnel_name: Option<String>,
+    ephemeral: Option<bool>,
+    temp_config: Option<std::path::PathBuf>,
+) -> Result<VpnStatusInfo, CommandError> {
     log::info!("Connecting to VPN: {:?} with config: {}", vpn_type, config_path);
     
+    let tunnel_name = match (&vpn_type, tunnel_name) {
+        (VpnType::WireGuard, Some(name)) => {
+            validate_tunnel_name(&name)?;
+            name
+        }
+        (VpnType::WireGuard, None) => wireguard_tunnel_name(&config_path),
+        (VpnType::OpenVPN, Some(_)) => {
+            return Err("Custom tunnel names are only supported for WireGuard".into());
+        }
+        (VpnType::OpenVPN, None) => "openvpn".to_string(),
+    };
+    let ephemeral = ephemeral.unwrap_or(false);
+    if ephemeral && vpn_type == VpnType::OpenVPN {
+        return Err("Ephemeral connections are only supported for WireGuard".into());
+    }
+    require_vpn_client(&vpn_type)?;
+    if sessions.tunnels.lock().await.contains_key(&tunnel_name) {
+        return Err(format!("Tunnel '{}' is already connected", tunnel_name).into());
+    }
+    
+    let cancel = CancellationToken::new();
+    if let Some(ref id) = request_id {
+        sessions.pending_connects.lock().await.insert(id.clone(), cancel.clone());
+    }
+    
+    let connect = async {
+        match vpn_type {
+            VpnType::WireGuard => connect_wireguard(&config_path, &tunnel_name, ephemeral).await,
+            VpnType::OpenVPN => connect_openvpn(&config_path).await,
+        }
+    };
+    
+    // Dropping the connect future kills the elevation helper it is waiting on
+    let result = tokio::select! {
+        result = connect => result,
+        _ = cancel.cancelled() => {
+            log::info!("Connect cancelled, rolling back anything that was started");
+            rollback_connect(&tunnel_name, &vpn_type).await;
+            Err("Connection cancelled.".to_string())
+        }
+    };
+    
+    if let Some(ref id) = request_id {
+        sessions.pending_connects.lock().await.remove(id);
+    }
+    
+    match &result {
+        Ok(info) => {
+            console::record(ConsoleLevel::Info, "vpn", format!("{:?} tunnel {}: {:?}", vpn_type, info.tunnel_name.as_deref().unwrap_or("-"), info.status));
+            let up = matches!(info.status, VpnStatus::Connected | VpnStatus::Connecting);
+            if let (true, Some(tunnel_name)) = (up, &info.tunnel_name) {
+                let config = std::fs::read_to_string(&config_path).ok().map(parse_vpn_config);
+                // WireGuard's DNS line may mix resolver addresses with search domains
+                let dns_servers = config.as_ref()
+                    .and_then(|c| c.dns.as_deref())
+                    .map(|dns| dns.split(',').filter_map(|entry| entry.trim().parse().ok()).collect())
+                    .unwrap_or_default();
+                sessions.tunnels.lock().await.insert(tunnel_name.clone(), sessions::ConnectedTunnel {
+                    vpn_type: vpn_type.clone(),
+                    address: config.as_ref().and_then(|c| c.address.clone()),
+                    allowed_ips: config.map(|c| c.allowed_ips).unwrap_or_default(),
+                    dns_servers,
+                    ephemeral,
+                    temp_config,
+                    config_path: config_path.clone(),
+                    connected_at: std::time::Instant::now(),
+                });
+            }
+        }
+        Err(e) => console::record(ConsoleLevel::Error, "vpn", format!("{:?} connect failed: {}", vpn_type, e)),
+    }
+    result.map_err(CommandError::from)
+}
+
+/// How long `reconnect_vpn` waits for the old service/process to go away
+const RECONNECT_TEARDOWN_SECS: u64 = 15;
+
+/// How long `reconnect_vpn` waits for an OpenVPN reconnect to report CONNECTED
+const RECONNECT_CONFIRM_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Serialize)]
+struct ReconnectEvent {
+    tunnel_name: String,
+    /// "disconnecting", "waiting", "connecting", "confirming", "rolling_back", "connected" or "failed"
+    stage: &'static str,
+    message: String,
+}
+
+/// Poll until `done` holds, for at most `secs` seconds
+async fn wait_until(secs: u64, done: impl Fn() -> bool) -> bool {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(secs);
+    while std::time::Instant::now() < deadline {
+        if done() {
+            return true;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+    }
+    done()
+}
+
+/// Disconnect a tunnel, wait until its service (WireGuard) or process (OpenVPN) is
+/// really gone and connect it again, emitting `vpn://reconnect` at each stage.
+/// `config_path` defaults to the config the tunnel was connected with; if a different
+/// config fails to connect, the previous one is restored.
+#[tauri::command]
+async fn reconnect_vpn(
+    app: AppHandle,
+    sessions: State<'_, sessions::ActiveSessions>,
+    tunnel_name: String,
+    vpn_type: VpnType,
+    config_path: Option<String>,
+) -> Result<VpnStatusInfo, CommandError> {
+    let progress = |stage: &'static str, message: String| {
+        log::info!("Reconnect '{}': {} - {}", tunnel_name, stage, message);
+        let _ = app.emit("vpn://reconnect", ReconnectEvent {
+            tunnel_name: tunnel_name.clone(),
+            stage,
+            message,
+        });
+    };
+    let fail = |message: String| {
+        progress("failed", message.clone());
+        console::record(ConsoleLevel::Error, "vpn", format!("Reconnect of {} failed: {}", tunnel_name, message));
+        CommandError::from(message)
+    };
+
+    require_vpn_client(&vpn_type)?;
+    let previous = sessions.tunnels.lock().await.get(&tunnel_name).cloned();
+    let previous_path = previous.as_ref().map(|t| t.config_path.clone());
+    let config_path = config_path.or_else(|| previous_path.clone())
+        .ok_or_else(|| fail(format!("Tunnel '{}' wasn't connected by this app, pass config_path", tunnel_name)))?;
+    let ephemeral = previous.as_ref().is_some_and(|t| t.ephemeral);
+    let temp_config = previous.as_ref().and_then(|t| t.temp_config.clone());
+
+    // Tear down directly rather than through disconnect_vpn, which would delete a generated config
+    progress("disconnecting", format!("Stopping {:?} tunnel", vpn_type));
+    let disconnected = match vpn_type {
+        VpnType::WireGuard => disconnect_wireguard(&tunnel_name).await,
+        VpnType::OpenVPN => disconnect_openvpn().await,
+    };
+    disconnected.map_err(|e| fail(format!("Disconnect failed, the tunnel is unchanged: {}", e)))?;
+
+    progress("waiting", "Waiting for the old connection to shut down".to_string());
+    let gone = match vpn_type {
+        VpnType::WireGuard => wait_until(RECONNECT_TEARDOWN_SECS, || !wireguard_service_exists(&tunnel_name)).await,
+        VpnType::OpenVPN => wait_until(RECONNECT_TEARDOWN_SECS, || get_openvpn_status().is_none()).await,
+    };
+    sessions.tunnels.lock().await.remove(&tunnel_name);
+    if !gone {
+        return Err(fail(format!("The old connection was still shutting down after {}s", RECONNECT_TEARDOWN_SECS)));
+    }
+
+    let name_arg = (vpn_type == VpnType::WireGuard).then(|| tunnel_name.clone());
+    progress("connecting", format!("Connecting with {}", config_path));
+    let mut result = connect_tunnel(&sessions, config_path.clone(), vpn_type.clone(), None, name_arg.clone(), Some(ephemeral), temp_config.clone()).await;
+
+    let connected = |result: &Result<VpnStatusInfo, CommandError>| {
+        result.as_ref().is_ok_and(|info| matches!(info.status, VpnStatus::Connected | VpnStatus::Connecting))
+    };
+    if !connected(&result) {
+        // Clear anything half started, then fall back to the config that worked before
+        progress("rolling_back", "Reconnect failed, cleaning up".to_string());
+        rollback_connect(&tunnel_name, &vpn_type).await;
+        sessions.tunnels.lock().await.remove(&tunnel_name);
+        if let Some(previous_path) = previous_path.filter(|p| *p != config_path) {
+            progress("rolling_back", format!("Restoring the previous config {}", previous_path));
+            let restored = connect_tunnel(&sessions, previous_path, vpn_type.clone(), None, name_arg, Some(ephemeral), temp_config.clone()).await;
+            if connected(&restored) {
+                let reason = result.err().map(|e| e.to_string()).unwrap_or_default();
+                return Err(fail(format!("New config failed ({}), reconnected with the previous one", reason)));
+            }
+        }
+        if let Some(path) = &temp_config {
+            private_files::remove_private(path);
+        }
+        let reason = result.err().map(|e| e.to_string()).unwrap_or_else(|| "tunnel did not come up".to_string());
+        return Err(fail(format!("Reconnect failed, the tunnel is down: {}", reason)));
+    }
+
+    // OpenVPN may still be negotiating when connect returns
+    if let Ok(info) = result.as_mut() {
+        if info.status == VpnStatus::Connecting {
+            progress("confirming", "Waiting for OpenVPN to finish connecting".to_string());
+            let up = wait_until(RECONNECT_CONFIRM_SECS, || {
+                get_openvpn_status().is_some_and(|status| status.status == VpnStatus::Connected)
+            }).await;
+            if up {
+                info.status = VpnStatus::Connected;
+            }
+        }
+        progress("connected", format!("Tunnel is {:?}", info.status));
+    }
+    result
+}
+
+/// Fail with `BinaryMissing` before any elevation prompt if the VPN client isn't installed
+fn require_vpn_client(vpn_type: &VpnType) -> Result<(), CommandError> {
+    let (installed, dependency) = match vpn_type {
+        VpnType::WireGuard => (std::path::Path::new(WIREGUARD_EXE).exists(), &errors::WIREGUARD),
+        #[cfg(windows)]
+        VpnType::OpenVPN => (std::path::Path::new(OPENVPN_EXE).exists(), &errors::OPENVPN),
+        #[cfg(unix)]
+        VpnType::OpenVPN => (find_openvpn_binary().is_some(), &errors::OPENVPN),
+    };
+    if installed {
+        Ok(())
+    } else {
+        Err(CommandError::missing(dependency))
+    }
+}
+
+/// Cancel a pending `connect_vpn` call. Returns false if no such request is in progress.
+#[tauri::command]
+async fn cancel_connect(
+    sessions: State<'_, sessions::ActiveSessions>,
+    request_id: String,
+) -> Result<bool, String> {
+    log::info!("Cancelling connect request: {}", request_id);
+    
+    match sessions.pending_connects.lock().await.get(&request_id) {
+        Some(cancel) => {
+            cancel.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Undo a connect that was cancelled before it was confirmed
+async fn rollback_connect(tunnel_name: &str, vpn_type: &VpnType) {
     match vpn_type {
-        VpnType::WireGuard => connect_wireguard(&config_path).await,
-        VpnType::OpenVPN => connect_openvpn(&config_path).await,
+        VpnType::WireGuard => {
+            // The elevated installer may still be finishing after its parent was killed
+            for _ in 0..6 {
+                if wireguard_service_exists(tunnel_name) {
+                    if let Err(e) = disconnect_wireguard(tunnel_name).await {
+                        log::warn!("Failed to remove tunnel '{}' after cancel: {}", tunnel_name, e);
+                    }
+                    return;
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            }
+        }
+        VpnType::OpenVPN => {
+            if get_openvpn_status().is_some() {
+                if let Err(e) = disconnect_openvpn().await {
+                    log::warn!("Failed to stop OpenVPN after cancel: {}", e);
+                }
+            }
+        }
     }
 }
 
-async fn connect_wireguard(config_path: &str) -> Result<VpnStatusInfo, String> {
-    // Extract tunnel name from config path
-    let tunnel_name = std::path::Path::new(config_path)
+/// Tunnel name is derived from the config file name
+fn wireguard_tunnel_name(config_path: &str) -> String {
+    std::path::Path::new(config_path)
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("claudetv_vpn")
-        .to_string();
+        .to_string()
+}
+
+/// Addresses bound to a network interface, without link-local ones
+fn interface_addresses(interface: &str) -> Vec<std::net::IpAddr> {
+    let output = if cfg!(windows) {
+        let script = format!(
+            "Get-NetIPAddress -InterfaceAlias '{}' -ErrorAction SilentlyContinue | ForEach-Object {{ $_.IPAddress }}",
+            interface.replace('\'', "''")
+        );
+        Command::new("powershell").args(["-NoProfile", "-Command", &script]).output()
+    } else if cfg!(target_os = "macos") {
+        Command::new("ifconfig").arg(interface).output()
+    } else {
+        Command::new("ip").args(["-o", "addr", "show", "dev", interface]).output()
+    };
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+    
+    let text = String::from_utf8_lossy(&output.stdout);
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    // PowerShell prints one address per line; ip/ifconfig put it after "inet"/"inet6"
+    let candidates: Vec<&str> = if cfg!(windows) {
+        tokens
+    } else {
+        tokens.windows(2)
+            .filter(|pair| pair[0] == "inet" || pair[0] == "inet6")
+            .map(|pair| pair[1])
+            .collect()
+    };
+    
+    let mut addresses: Vec<std::net::IpAddr> = candidates.iter()
+        .filter_map(|token| token.split(['/', '%']).next()?.parse().ok())
+        .filter(|ip| match ip {
+            std::net::IpAddr::V4(v4) => !v4.is_link_local(),
+            std::net::IpAddr::V6(v6) => (v6.segments()[0] & 0xffc0) != 0xfe80,
+        })
+        .collect();
+    addresses.dedup();
+    addresses
+}
+
+/// Tunnel addresses confirmed on the live interface. With the config's `Address`
+/// only those entries are reported, otherwise whatever the interface holds.
+fn assigned_ip(tunnel_name: &str, configured: Option<&str>) -> Option<String> {
+    let live = interface_addresses(tunnel_name);
+    let configured: Vec<std::net::IpAddr> = configured.unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| entry.trim().split('/').next()?.parse().ok())
+        .collect();
+    
+    let addresses: Vec<String> = if configured.is_empty() {
+        live.iter().map(|ip| ip.to_string()).collect()
+    } else {
+        configured.iter().filter(|ip| live.contains(ip)).map(|ip| ip.to_string()).collect()
+    };
+    (!addresses.is_empty()).then(|| addresses.join(", "))
+}
+
+/// WireGuard tunnel names become part of a Windows service name and the config file name
+fn validate_tunnel_name(name: &str) -> Result<(), String> {
+    let valid_chars = name.chars().all(|c| c.is_ascii_alphanumeric() || "_=+.-".contains(c));
+    if name.is_empty() || name.len() > 32 || !valid_chars || name.starts_with('.') {
+        return Err(format!(
+            "Invalid tunnel name '{}': use 1-32 letters, digits or _=+.-",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// wireguard.exe names the tunnel after the config file, so a config installed under
+/// a different name is copied to `<tunnel_name>.conf` in a private directory first
+fn stage_wireguard_config(config_path: &str, tunnel_name: &str) -> Result<String, String> {
+    if wireguard_tunnel_name(config_path) == tunnel_name {
+        return Ok(config_path.to_string());
+    }
+    
+    let content = std::fs::read(config_path)
+        .map_err(|e| format!("Failed to read config for tunnel '{}': {}", tunnel_name, e))?;
+    let staged = private_files::private_dir(private_files::TUNNEL_CONFIG_DIR)?.join(format!("{}.conf", tunnel_name));
+    private_files::write_private(&staged, &content)?;
+    
+    Ok(staged.to_string_lossy().to_string())
+}
+
+/// True if a WireGuard tunnel service is installed (running or not)
+fn wireguard_service_exists(tunnel_name: &str) -> bool {
+    let service_name = format!("WireGuardTunnel${}", tunnel_name);
+    Command::new("sc")
+        .args(["query", &service_name])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("SERVICE_NAME"))
+        .unwrap_or(false)
+}
+
+async fn connect_wireguard(config_path: &str, tunnel_name: &str, ephemeral: bool) -> Result<VpnStatusInfo, String> {
+    let config_path = &stage_wireguard_config(config_path, tunnel_name)?;
+    let tunnel_name = tunnel_name.to_string();
     
     // WireGuard on Windows: wireguard.exe /installtunnelservice <config_path>
-    let wireguard_path = r"C:\Program Files\WireGuard\wireguard.exe";
+    let wireguard_path = WIREGUARD_EXE;
     
     if !std::path::Path::new(wireguard_path).exists() {
-        return Err("WireGuard is not installed. Please install WireGuard from https://www.wireguard.com/install/".to_string());
+        return Err(errors::WIREGUARD.message.to_string());
     }
     
     // Use PowerShell Start-Process with -Verb RunAs to trigger UAC elevation
     // ArgumentList needs the full argument string including the config path
     let escaped_path = config_path.replace("'", "''").replace('"', r#"\""#);
-    let ps_command = format!(
-        r#"Start-Process -FilePath '{}' -ArgumentList '/installtunnelservice "{}"' -Verb RunAs -Wait -WindowStyle Hidden"#,
-        wireguard_path,
-        escaped_path
-    );
+    let ps_command = if ephemeral {
+        // The service is installed to start automatically; switch it to manual in the
+        // same elevated session so there's a single UAC prompt
+        let script = format!(
+            "& '{}' /installtunnelservice '{}'\nif ($LASTEXITCODE -eq 0) {{ sc.exe config 'WireGuardTunnel${}' start= demand | Out-Null }}",
+            wireguard_path,
+            config_path.replace("'", "''"),
+            tunnel_name
+        );
+        let encoded: Vec<u8> = script.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        format!(
+            "Start-Process -FilePath 'powershell' -ArgumentList '-NoProfile','-EncodedCommand','{}' -Verb RunAs -Wait -WindowStyle Hidden",
+            general_purpose::STANDARD.encode(encoded)
+        )
+    } else {
+        format!(
+            r#"Start-Process -FilePath '{}' -ArgumentList '/installtunnelservice "{}"' -Verb RunAs -Wait -WindowStyle Hidden"#,
+            wireguard_path,
+            escaped_path
+        )
+    };
     
     log::info!("Executing WireGuard with elevation: {}", ps_command);
     
-    let output = Command::new("powershell")
-        .args(&["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &ps_command])
+    // Async so a cancelled connect stops waiting on the UAC prompt
+    let output = tokio::process::Command::new("powershell")
+        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &ps_command])
+        .kill_on_drop(true)
         .output()
+        .await
         .map_err(|e| format!("Failed to start WireGuard: {}", e))?;
     
     if !output.status.success() {
@@ -295,9 +1016,13 @@ async fn connect_wireguard(config_path: &str) -> Result<VpnStatusInfo, String> {
         let stdout = String::from_utf8_lossy(&check.stdout);
         if stdout.contains("RUNNING") {
             log::info!("WireGuard tunnel '{}' started successfully", tunnel_name);
+            let configured = std::fs::read_to_string(config_path)
+                .ok()
+                .and_then(|content| parse_wireguard_config(&content).address);
             return Ok(VpnStatusInfo {
                 status: VpnStatus::Connected,
                 vpn_type: Some(VpnType::WireGuard),
+                assigned_ip: assigned_ip(&tunnel_name, configured.as_deref()),
                 tunnel_name: Some(tunnel_name),
                 error: None,
             });
@@ -311,22 +1036,28 @@ async fn connect_wireguard(config_path: &str) -> Result<VpnStatusInfo, String> {
         status: VpnStatus::Connected,
         vpn_type: Some(VpnType::WireGuard),
         tunnel_name: Some(tunnel_name),
+        assigned_ip: None,
         error: None,
     })
 }
 
+#[cfg(windows)]
 async fn connect_openvpn(config_path: &str) -> Result<VpnStatusInfo, String> {
-    let openvpn_path = r"C:\Program Files\OpenVPN\bin\openvpn.exe";
+    let openvpn_path = OPENVPN_EXE;
     
     if !std::path::Path::new(openvpn_path).exists() {
-        return Err("OpenVPN is not installed. Please install OpenVPN from https://openvpn.net/community-downloads/".to_string());
+        return Err(errors::OPENVPN.message.to_string());
     }
     
+    let (status_file, status_interval) = openvpn_status_args()?;
+    
     // Use PowerShell Start-Process with -Verb RunAs to trigger UAC elevation
     let ps_command = format!(
-        "Start-Process -FilePath '{}' -ArgumentList '--config', '{}' -Verb RunAs -WindowStyle Hidden",
+        r#"Start-Process -FilePath '{}' -ArgumentList '--config', '{}', '--status', '"{}"', '{}' -Verb RunAs -WindowStyle Hidden"#,
         openvpn_path,
-        config_path.replace("'", "''")
+        config_path.replace("'", "''"),
+        status_file.replace("'", "''"),
+        status_interval
     );
     
     log::info!("Executing OpenVPN with elevation: {}", ps_command);
@@ -355,23 +1086,121 @@ async fn connect_openvpn(config_path: &str) -> Result<VpnStatusInfo, String> {
         status: VpnStatus::Connected,
         vpn_type: Some(VpnType::OpenVPN),
         tunnel_name: Some("openvpn".to_string()),
+        assigned_ip: None,
         error: None,
     })
 }
 
+/// Uninstall the tunnels connected with `ephemeral`, on app exit
+async fn remove_ephemeral_tunnels(sessions: &sessions::ActiveSessions) {
+    let tunnels = sessions.tunnels.lock().await.clone();
+    // Only WireGuard tunnels can be ephemeral
+    for tunnel_name in tunnels.iter().filter(|(_, t)| t.ephemeral).map(|(name, _)| name) {
+        log::info!("Removing ephemeral tunnel '{}' on exit", tunnel_name);
+        if let Err(e) = disconnect_wireguard(tunnel_name).await {
+            log::warn!("Failed to remove ephemeral tunnel '{}': {}", tunnel_name, e);
+        }
+        remove_tunnel_config(tunnel_name, &VpnType::WireGuard, tunnels[tunnel_name].temp_config.as_deref());
+    }
+}
+
+/// OpenVPN rewrites its `--status` file this often
+const OPENVPN_STATUS_INTERVAL_SECS: u32 = 5;
+
+/// `--status` file of the OpenVPN instance we start. It lives in our own private
+/// directory so it can be deleted even though the root-owned daemon wrote it.
+fn openvpn_status_file() -> std::path::PathBuf {
+    temp_dirs::root().join(private_files::TUNNEL_CONFIG_DIR).join("openvpn.status")
+}
+
+/// `--status` arguments for a fresh status file, clearing one left by a previous run
+fn openvpn_status_args() -> Result<(String, String), String> {
+    private_files::private_dir(private_files::TUNNEL_CONFIG_DIR)?;
+    let path = openvpn_status_file();
+    private_files::remove_private(&path);
+    Ok((path.to_string_lossy().to_string(), OPENVPN_STATUS_INTERVAL_SECS.to_string()))
+}
+
+/// Byte counters from an OpenVPN status file ("OpenVPN STATISTICS" section, `name,value` lines)
+fn parse_openvpn_status(content: &str) -> std::collections::HashMap<String, u64> {
+    content.lines()
+        .filter_map(|line| line.rsplit_once(','))
+        .filter_map(|(name, value)| Some((name.trim().to_string(), value.trim().parse().ok()?)))
+        .collect()
+}
+
+/// Traffic counters of the OpenVPN tunnel, read from the status file it writes
+/// every few seconds. Only available for connections started by this app.
+#[tauri::command]
+async fn get_openvpn_stats(sessions: State<'_, sessions::ActiveSessions>) -> Result<OpenVpnStats, String> {
+    let path = openvpn_status_file();
+    let content = std::fs::read_to_string(&path)
+        .map_err(|_| "No OpenVPN statistics available. Is an OpenVPN connection from this app running?".to_string())?;
+    let counters = parse_openvpn_status(&content);
+    let counter = |name: &str| counters.get(name).copied().unwrap_or_default();
+
+    // The status file's birth time is missing on many filesystems and OpenVPN reuses the file
+    let uptime_secs = sessions.tunnels.lock().await.values()
+        .find(|tunnel| tunnel.vpn_type == VpnType::OpenVPN)
+        .map(|tunnel| tunnel.connected_at.elapsed().as_secs());
+    let status_age_secs = std::fs::metadata(&path).and_then(|m| m.modified()).ok()
+        .and_then(|time| time.elapsed().ok())
+        .map(|age| age.as_secs());
+    Ok(OpenVpnStats {
+        status: get_openvpn_status().map_or(VpnStatus::Disconnected, |info| info.status),
+        rx_bytes: counter("TCP/UDP read bytes"),
+        tx_bytes: counter("TCP/UDP write bytes"),
+        tun_read_bytes: counter("TUN/TAP read bytes"),
+        tun_write_bytes: counter("TUN/TAP write bytes"),
+        uptime_secs,
+        status_age_secs,
+    })
+}
+
+/// Delete the staged or generated config of a disconnected tunnel
+fn remove_tunnel_config(tunnel_name: &str, vpn_type: &VpnType, temp_config: Option<&std::path::Path>) {
+    if let Some(path) = temp_config {
+        private_files::remove_private(path);
+    }
+    // Also covers tunnels connected before an app restart, which aren't tracked any more
+    let file_name = match vpn_type {
+        VpnType::WireGuard => format!("{}.conf", tunnel_name),
+        VpnType::OpenVPN => "openvpn.ovpn".to_string(),
+    };
+    private_files::remove_private(&temp_dirs::root().join(private_files::TUNNEL_CONFIG_DIR).join(file_name));
+}
+
 /// Disconnect from VPN
 #[tauri::command]
-async fn disconnect_vpn(tunnel_name: String, vpn_type: VpnType) -> Result<VpnStatusInfo, String> {
+async fn disconnect_vpn(
+    sessions: State<'_, sessions::ActiveSessions>,
+    tunnel_name: String,
+    vpn_type: VpnType,
+) -> Result<VpnStatusInfo, CommandError> {
     log::info!("Disconnecting VPN: {:?}, tunnel: {}", vpn_type, tunnel_name);
     
-    match vpn_type {
+    // Stopping OpenVPN only signals the running process, so only WireGuard needs its client
+    if vpn_type == VpnType::WireGuard {
+        require_vpn_client(&vpn_type)?;
+    }
+    
+    let result = match vpn_type {
         VpnType::WireGuard => disconnect_wireguard(&tunnel_name).await,
         VpnType::OpenVPN => disconnect_openvpn().await,
+    };
+    if result.is_ok() {
+        let tunnel = sessions.tunnels.lock().await.remove(&tunnel_name);
+        remove_tunnel_config(&tunnel_name, &vpn_type, tunnel.and_then(|t| t.temp_config).as_deref());
     }
+    match &result {
+        Ok(info) => console::record(ConsoleLevel::Info, "vpn", format!("{:?} tunnel {}: {:?}", vpn_type, tunnel_name, info.status)),
+        Err(e) => console::record(ConsoleLevel::Error, "vpn", format!("{:?} disconnect failed: {}", vpn_type, e)),
+    }
+    result.map_err(CommandError::from)
 }
 
 async fn disconnect_wireguard(tunnel_name: &str) -> Result<VpnStatusInfo, String> {
-    let wireguard_path = r"C:\Program Files\WireGuard\wireguard.exe";
+    let wireguard_path = WIREGUARD_EXE;
     
     // Use PowerShell Start-Process with -Verb RunAs to trigger UAC elevation
     let ps_command = format!(
@@ -400,10 +1229,12 @@ async fn disconnect_wireguard(tunnel_name: &str) -> Result<VpnStatusInfo, String
         status: VpnStatus::Disconnected,
         vpn_type: None,
         tunnel_name: None,
+        assigned_ip: None,
         error: None,
     })
 }
 
+#[cfg(windows)]
 async fn disconnect_openvpn() -> Result<VpnStatusInfo, String> {
     // Kill OpenVPN process on Windows - use elevation for admin-started processes
     let ps_command = "Stop-Process -Name 'openvpn' -Force -ErrorAction SilentlyContinue";
@@ -416,6 +1247,7 @@ async fn disconnect_openvpn() -> Result<VpnStatusInfo, String> {
     if !output.status.success() {
         log::warn!("OpenVPN may not have been running");
     }
+    private_files::remove_private(&openvpn_status_file());
     
     log::info!("OpenVPN disconnected");
     
@@ -423,13 +1255,97 @@ async fn disconnect_openvpn() -> Result<VpnStatusInfo, String> {
         status: VpnStatus::Disconnected,
         vpn_type: None,
         tunnel_name: None,
+        assigned_ip: None,
         error: None,
     })
 }
 
+/// List installed WireGuard tunnel services as (tunnel name, running)
+fn list_wireguard_services() -> Result<Vec<(String, bool)>, String> {
+    let output = Command::new("sc")
+        .args(["query", "type=", "service", "state=", "all"])
+        .output()
+        .map_err(|e| format!("Failed to query services: {}", e))?;
+    
+    // Blocks of "SERVICE_NAME: ..." followed by indented fields including "STATE : 4  RUNNING"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut services: Vec<(String, bool)> = Vec::new();
+    let mut in_wireguard_block = false;
+    for line in stdout.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("SERVICE_NAME:") {
+            let tunnel = name.trim().strip_prefix("WireGuardTunnel$");
+            in_wireguard_block = tunnel.is_some();
+            if let Some(tunnel) = tunnel {
+                services.push((tunnel.to_string(), false));
+            }
+        } else if in_wireguard_block && line.starts_with("STATE") {
+            if let Some(last) = services.last_mut() {
+                last.1 = line.contains("RUNNING");
+            }
+        }
+    }
+    
+    Ok(services)
+}
+
+/// Find WireGuard tunnel services left behind (e.g. after a crash) and uninstall
+/// the ones whose name starts with `prefix` (default "claudetv").
+/// Don't call this while a tunnel with that prefix is meant to stay connected.
+#[tauri::command]
+async fn cleanup_orphaned_tunnels(prefix: Option<String>) -> Result<TunnelCleanupSummary, String> {
+    let prefix = prefix.unwrap_or_else(|| "claudetv".to_string()).to_lowercase();
+    log::info!("Looking for orphaned WireGuard tunnels with prefix '{}'", prefix);
+    
+    let mut tunnels = Vec::new();
+    for (tunnel_name, running) in list_wireguard_services()? {
+        let mut info = WireGuardServiceInfo {
+            tunnel_name,
+            running,
+            removed: false,
+            error: None,
+        };
+        
+        if info.tunnel_name.to_lowercase().starts_with(&prefix) {
+            log::info!("Removing orphaned tunnel '{}' (running: {})", info.tunnel_name, running);
+            match disconnect_wireguard(&info.tunnel_name).await {
+                Ok(_) => {
+                    info.removed = !wireguard_service_exists(&info.tunnel_name);
+                    if !info.removed {
+                        info.error = Some("Service is still installed (elevation may have been cancelled)".to_string());
+                    }
+                }
+                Err(e) => info.error = Some(e),
+            }
+        }
+        
+        tunnels.push(info);
+    }
+    
+    let removed_count = tunnels.iter().filter(|t| t.removed).count();
+    log::info!("Tunnel cleanup: {} found, {} removed", tunnels.len(), removed_count);
+    
+    Ok(TunnelCleanupSummary {
+        tunnels,
+        removed_count,
+    })
+}
+
 /// Get current VPN status
 #[tauri::command]
-fn get_vpn_status(tunnel_name: Option<String>, vpn_type: Option<VpnType>) -> VpnStatusInfo {
+async fn get_vpn_status(
+    sessions: State<'_, sessions::ActiveSessions>,
+    tunnel_name: Option<String>,
+    vpn_type: Option<VpnType>,
+) -> Result<VpnStatusInfo, String> {
+    Ok(vpn_status(&sessions, tunnel_name, vpn_type).await)
+}
+
+pub(crate) async fn vpn_status(
+    sessions: &sessions::ActiveSessions,
+    tunnel_name: Option<String>,
+    vpn_type: Option<VpnType>,
+) -> VpnStatusInfo {
     log::info!("Checking VPN status for tunnel: {:?}", tunnel_name);
     
     // Check WireGuard status
@@ -443,10 +1359,13 @@ fn get_vpn_status(tunnel_name: Option<String>, vpn_type: Option<VpnType>) -> Vpn
             if let Ok(output) = output {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 if stdout.contains("RUNNING") {
+                    // The configured address picks ours when the interface has several
+                    let configured = sessions.tunnels.lock().await.get(name).and_then(|t| t.address.clone());
                     return VpnStatusInfo {
                         status: VpnStatus::Connected,
                         vpn_type: Some(VpnType::WireGuard),
                         tunnel_name: Some(name.clone()),
+                        assigned_ip: assigned_ip(name, configured.as_deref()),
                         error: None,
                     };
                 }
@@ -454,48 +1373,695 @@ fn get_vpn_status(tunnel_name: Option<String>, vpn_type: Option<VpnType>) -> Vpn
         }
     }
     
-    // Check OpenVPN status (check if process is running)
+    // Check OpenVPN status
     if vpn_type == Some(VpnType::OpenVPN) || vpn_type.is_none() {
-        let output = Command::new("tasklist")
-            .args(&["/FI", "IMAGENAME eq openvpn.exe"])
-            .output();
-        
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if stdout.contains("openvpn.exe") {
-                return VpnStatusInfo {
+        if let Some(status) = get_openvpn_status() {
+            return status;
+        }
+    }
+    
+    VpnStatusInfo {
+        status: VpnStatus::Disconnected,
+        vpn_type: None,
+        tunnel_name: None,
+        assigned_ip: None,
+        error: None,
+    }
+}
+
+/// WireGuard drops session keys this long after a handshake (Reject-After-Time),
+/// so a peer without a newer one is dead
+const HANDSHAKE_STALE_SECS: u64 = 180;
+
+/// Parse `wg show <interface> dump`: an interface line, then one tab separated line per peer
+fn parse_wg_dump(tunnel_name: &str, dump: &str, now: u64) -> WireGuardPeersInfo {
+    let mut lines = dump.lines().filter(|line| !line.trim().is_empty());
+    let interface: Vec<&str> = lines.next().unwrap_or_default().split('\t').collect();
+    let optional = |value: Option<&&str>| value.filter(|v| !v.is_empty() && **v != "(none)" && **v != "off").map(|v| v.to_string());
+
+    let peers: Vec<WireGuardPeer> = lines
+        .map(|line| line.split('\t').collect::<Vec<&str>>())
+        .filter(|fields| fields.len() >= 8)
+        .map(|fields| {
+            let latest_handshake = fields[4].parse().ok().filter(|t| *t > 0);
+            let handshake_age_secs = latest_handshake.map(|t: u64| now.saturating_sub(t));
+            WireGuardPeer {
+                public_key: fields[0].to_string(),
+                endpoint: optional(fields.get(2)),
+                allowed_ips: optional(fields.get(3))
+                    .map(|ips| ips.split(',').map(|ip| ip.trim().to_string()).collect())
+                    .unwrap_or_default(),
+                latest_handshake,
+                handshake_age_secs,
+                rx_bytes: fields[5].parse().unwrap_or_default(),
+                tx_bytes: fields[6].parse().unwrap_or_default(),
+                persistent_keepalive: fields[7].parse().ok(),
+                active: handshake_age_secs.is_some_and(|age| age < HANDSHAKE_STALE_SECS),
+            }
+        })
+        .collect();
+
+    WireGuardPeersInfo {
+        tunnel_name: tunnel_name.to_string(),
+        status: if peers.iter().any(|p| p.active) { VpnStatus::Connected } else { VpnStatus::Connecting },
+        public_key: optional(interface.get(1)),
+        listen_port: interface.get(2).and_then(|port| port.parse().ok()),
+        peers,
+    }
+}
+
+/// Per-peer state of a running WireGuard tunnel from `wg show dump`: endpoint, allowed
+/// IPs, handshake age and transfer counters. Reading it needs admin rights, on Unix
+/// this falls back to non-interactive sudo.
+#[tauri::command]
+async fn get_wireguard_peers(tunnel_name: String) -> Result<WireGuardPeersInfo, CommandError> {
+    validate_tunnel_name(&tunnel_name)?;
+    let args = ["show", tunnel_name.as_str(), "dump"];
+    let output = match tokio::process::Command::new(WG_EXE).args(args).output().await {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(CommandError::missing(&errors::WIREGUARD)),
+        Err(e) => return Err(format!("Failed to run wg: {}", e).into()),
+    };
+    #[cfg(unix)]
+    let output = match output.status.success() {
+        true => output,
+        false => tokio::process::Command::new("sudo").arg("-n").arg(WG_EXE).args(args).output().await
+            .map_err(|e| format!("Failed to run wg: {}", e))?,
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = match stderr.to_lowercase() {
+            e if e.contains("no such device") || e.contains("does not exist") => format!("Tunnel '{}' is not running", tunnel_name),
+            e if e.contains("permission") || e.contains("access is denied") || e.contains("password") => {
+                "Reading WireGuard peers requires administrator rights".to_string()
+            }
+            _ => format!("wg show failed: {}", stderr.trim()),
+        };
+        return Err(detail.into());
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let info = parse_wg_dump(&tunnel_name, &String::from_utf8_lossy(&output.stdout), now);
+    log::info!(
+        "Tunnel '{}': {}/{} peers active",
+        tunnel_name, info.peers.iter().filter(|p| p.active).count(), info.peers.len()
+    );
+    Ok(info)
+}
+
+/// Check if OpenVPN is running (Windows: check if process is running)
+#[cfg(windows)]
+fn get_openvpn_status() -> Option<VpnStatusInfo> {
+    let output = Command::new("tasklist")
+        .args(&["/FI", "IMAGENAME eq openvpn.exe"])
+        .output()
+        .ok()?;
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !stdout.contains("openvpn.exe") {
+        return None;
+    }
+    
+    Some(VpnStatusInfo {
+        status: VpnStatus::Connected,
+        vpn_type: Some(VpnType::OpenVPN),
+        tunnel_name: Some("openvpn".to_string()),
+        assigned_ip: None,
+        error: None,
+    })
+}
+
+/// Check the OpenVPN instance we started (Linux/macOS: management socket, then PID file)
+#[cfg(unix)]
+fn get_openvpn_status() -> Option<VpnStatusInfo> {
+    let status = match openvpn_management_command("state") {
+        Some(state) if state.contains(",CONNECTED,") => VpnStatus::Connected,
+        Some(_) => VpnStatus::Connecting,
+        // No management interface, but the process is alive
+        None => {
+            read_openvpn_pid()?;
+            VpnStatus::Connected
+        }
+    };
+    
+    Some(VpnStatusInfo {
+        status,
+        vpn_type: Some(VpnType::OpenVPN),
+        tunnel_name: Some("openvpn".to_string()),
+        assigned_ip: None,
+        error: None,
+    })
+}
+
+// ========================================
+// Elevation Pre-flight
+// ========================================
+
+/// Run a harmless command through the same elevation path connects use, so the UI
+/// can tell up front whether admin rights will be granted
+#[tauri::command]
+async fn check_elevation_available() -> ElevationCheck {
+    log::info!("Checking whether elevation is available...");
+    let check = elevation_check().await;
+    log::info!("Elevation check: {:?}", check.status);
+    check
+}
+
+#[cfg(windows)]
+async fn elevation_check() -> ElevationCheck {
+    let powershell = |script: &'static str| async move {
+        tokio::process::Command::new("powershell")
+            .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", script])
+            .kill_on_drop(true)
+            .output()
+            .await
+    };
+    
+    let is_admin = powershell(
+        "([Security.Principal.WindowsPrincipal][Security.Principal.WindowsIdentity]::GetCurrent()).IsInRole([Security.Principal.WindowsBuiltInRole]::Administrator)",
+    ).await;
+    if is_admin.is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "True") {
+        return ElevationCheck { status: ElevationStatus::AlreadyElevated, detail: None };
+    }
+    
+    // ConsentPromptBehaviorUser = 0 silently denies elevation requests from standard users
+    let policy = Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System",
+            "/v", "ConsentPromptBehaviorUser",
+        ])
+        .output();
+    if let Ok(policy) = policy {
+        let stdout = String::from_utf8_lossy(&policy.stdout);
+        if stdout.split_whitespace().last() == Some("0x0") {
+            return ElevationCheck {
+                status: ElevationStatus::BlockedByPolicy,
+                detail: Some("Group policy automatically denies elevation requests for standard users".to_string()),
+            };
+        }
+    }
+    
+    let output = powershell(
+        "Start-Process -FilePath 'cmd.exe' -ArgumentList '/c exit 0' -Verb RunAs -Wait -WindowStyle Hidden",
+    ).await;
+    match output {
+        Ok(output) if output.status.success() => ElevationCheck { status: ElevationStatus::Available, detail: None },
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let status = if stderr.contains("canceled") || stderr.contains("cancelled") {
+                ElevationStatus::Cancelled
+            } else if stderr.contains("administrator") || stderr.contains("policy") {
+                ElevationStatus::BlockedByPolicy
+            } else {
+                ElevationStatus::Unavailable
+            };
+            ElevationCheck { status, detail: Some(stderr.trim().to_string()) }
+        }
+        Err(e) => ElevationCheck {
+            status: ElevationStatus::Unavailable,
+            detail: Some(format!("Failed to run PowerShell: {}", e)),
+        },
+    }
+}
+
+#[cfg(unix)]
+async fn elevation_check() -> ElevationCheck {
+    let uid = Command::new("id").arg("-u").output();
+    if uid.is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "0") {
+        return ElevationCheck { status: ElevationStatus::AlreadyElevated, detail: None };
+    }
+    
+    let uses_sudo = !cfg!(target_os = "macos") && find_in_path("pkexec").is_none();
+    let output = tokio::process::Command::from(elevated_command("true", &[]))
+        .kill_on_drop(true)
+        .output()
+        .await;
+    match output {
+        Ok(output) if output.status.success() => ElevationCheck { status: ElevationStatus::Available, detail: None },
+        // Non-interactive sudo can't prompt, so a password requirement isn't a cancel
+        Ok(output) if uses_sudo => ElevationCheck {
+            status: ElevationStatus::Unavailable,
+            detail: Some(format!(
+                "pkexec is not installed and sudo needs a password: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        },
+        Ok(output) if is_elevation_cancelled(&output) => ElevationCheck {
+            status: ElevationStatus::Cancelled,
+            detail: None,
+        },
+        // pkexec exits with 127 when polkit refuses the action outright
+        Ok(output) => ElevationCheck {
+            status: if output.status.code() == Some(127) {
+                ElevationStatus::BlockedByPolicy
+            } else {
+                ElevationStatus::Unavailable
+            },
+            detail: Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        },
+        Err(e) => ElevationCheck {
+            status: ElevationStatus::Unavailable,
+            detail: Some(format!("Failed to run elevation helper: {}", e)),
+        },
+    }
+}
+
+// ========================================
+// OpenVPN on Linux/macOS
+// ========================================
+
+/// Common install locations; sbin directories are often missing from a user's PATH
+#[cfg(unix)]
+const OPENVPN_UNIX_PATHS: &[&str] = &[
+    "/usr/sbin/openvpn",
+    "/usr/local/sbin/openvpn",
+    "/opt/homebrew/sbin/openvpn",
+    "/opt/local/sbin/openvpn",
+];
+
+#[cfg(unix)]
+fn find_in_path(binary: &str) -> Option<String> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+#[cfg(unix)]
+fn find_openvpn_binary() -> Option<String> {
+    OPENVPN_UNIX_PATHS.iter()
+        .find(|p| std::path::Path::new(p).exists())
+        .map(|s| s.to_string())
+        .or_else(|| find_in_path("openvpn"))
+}
+
+/// PID file and management socket of our OpenVPN daemon. They live in the user's
+/// private tunnel directory, not the shared temp folder, where another user could plant
+/// a symlink for root to write through or bind the socket first.
+#[cfg(unix)]
+fn openvpn_pid_file() -> Result<std::path::PathBuf, String> {
+    Ok(private_files::private_dir(private_files::TUNNEL_CONFIG_DIR)?.join("openvpn.pid"))
+}
+
+#[cfg(unix)]
+fn openvpn_management_socket() -> Result<std::path::PathBuf, String> {
+    Ok(private_files::private_dir(private_files::TUNNEL_CONFIG_DIR)?.join("openvpn.sock"))
+}
+
+/// Clear what a previous daemon left at `path`. Only this user and root can write to the
+/// private directory, but anything other than a plain file or socket is refused.
+#[cfg(unix)]
+fn clear_openvpn_runtime_file(path: &std::path::Path) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+    match std::fs::symlink_metadata(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to inspect {}: {}", path.display(), e)),
+        Ok(metadata) if metadata.is_file() || metadata.file_type().is_socket() => std::fs::remove_file(path)
+            .map_err(|e| format!("Failed to remove stale {}: {}", path.display(), e)),
+        Ok(_) => Err(format!("{} already exists and is not a file, refusing to use it", path.display())),
+    }
+}
+
+/// Quote an argument for `sh -c`
+#[cfg(unix)]
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Build a command that runs `program` with administrator privileges.
+/// macOS uses the native password dialog via osascript, Linux prefers pkexec
+/// (graphical polkit prompt) and falls back to non-interactive sudo.
+#[cfg(unix)]
+fn elevated_command(program: &str, args: &[&str]) -> Command {
+    if cfg!(target_os = "macos") {
+        let shell_line = std::iter::once(program)
+            .chain(args.iter().copied())
+            .map(shell_quote)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let script = format!(
+            "do shell script \"{}\" with administrator privileges",
+            shell_line.replace('\\', r"\\").replace('"', r#"\""#)
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else if find_in_path("pkexec").is_some() {
+        let mut command = Command::new("pkexec");
+        command.arg(program).args(args);
+        command
+    } else {
+        let mut command = Command::new("sudo");
+        command.arg("-n").arg(program).args(args);
+        command
+    }
+}
+
+/// True if the elevation prompt was dismissed rather than the command failing
+#[cfg(unix)]
+fn is_elevation_cancelled(output: &std::process::Output) -> bool {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // pkexec exits with 126 when the dialog is dismissed, osascript reports error -128
+    output.status.code() == Some(126)
+        || stderr.contains("User canceled")
+        || stderr.contains("(-128)")
+        || stderr.contains("a password is required")
+}
+
+/// Read the PID of the OpenVPN daemon we started, if it is still running
+#[cfg(unix)]
+fn read_openvpn_pid() -> Option<u32> {
+    let pid: u32 = std::fs::read_to_string(openvpn_pid_file().ok()?).ok()?.trim().parse().ok()?;
+    
+    // Guard against a stale PID file whose PID was reused by another process
+    let output = Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "comm="])
+        .output()
+        .ok()?;
+    
+    String::from_utf8_lossy(&output.stdout).contains("openvpn").then_some(pid)
+}
+
+/// Send a command to the OpenVPN management interface and return its reply
+#[cfg(unix)]
+fn openvpn_management_command(command: &str) -> Option<String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    
+    let mut stream = UnixStream::connect(openvpn_management_socket().ok()?).ok()?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(2))).ok()?;
+    stream.write_all(format!("{}\n", command).as_bytes()).ok()?;
+    
+    let mut reply = String::new();
+    for line in BufReader::new(stream).lines() {
+        let line = line.ok()?;
+        // Skip the greeting and any real-time notifications
+        if line.starts_with(">") {
+            continue;
+        }
+        let done = line == "END" || line.starts_with("SUCCESS:") || line.starts_with("ERROR:");
+        reply.push_str(&line);
+        reply.push('\n');
+        if done {
+            break;
+        }
+    }
+    
+    Some(reply)
+}
+
+/// Login name of the user the app runs as, looked up from the uid since `$USER` may be unset
+#[cfg(unix)]
+fn current_user_name() -> Result<String, String> {
+    let uid = temp_dirs::current_uid();
+    let mut buffer: Vec<libc::c_char> = vec![0; 16 * 1024];
+    // SAFETY: an all-zero passwd is valid (null pointers, zero ids) and is only read on success
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local and the length matches `buffer`
+    let code = unsafe { libc::getpwuid_r(uid, &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if code != 0 || result.is_null() || passwd.pw_name.is_null() {
+        return Err(format!("Failed to look up the user name for uid {}", uid));
+    }
+    // SAFETY: on success pw_name is a NUL-terminated string inside `buffer`, which is still alive
+    let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+    Ok(name.to_string_lossy().into_owned())
+}
+
+#[cfg(unix)]
+async fn connect_openvpn(config_path: &str) -> Result<VpnStatusInfo, String> {
+    let openvpn_path = find_openvpn_binary()
+        .ok_or_else(|| errors::OPENVPN.message.to_string())?;
+    
+    if read_openvpn_pid().is_some() {
+        return Err("An OpenVPN connection started by this app is already running. Disconnect it first.".to_string());
+    }
+    let pid_file = openvpn_pid_file()?;
+    let socket = openvpn_management_socket()?;
+    clear_openvpn_runtime_file(&pid_file)?;
+    clear_openvpn_runtime_file(&socket)?;
+    let pid_file = pid_file.to_string_lossy().to_string();
+    let socket = socket.to_string_lossy().to_string();
+    // The daemon runs as root, so explicitly allow our user on the management socket
+    let user = current_user_name()?;
+    let (status_file, status_interval) = openvpn_status_args()?;
+    
+    let args = [
+        "--config", config_path,
+        "--daemon", "claudetv-openvpn",
+        "--writepid", &pid_file,
+        "--management", &socket, "unix",
+        "--management-client-user", &user,
+        "--status", &status_file, &status_interval,
+    ];
+    
+    log::info!("Executing OpenVPN with elevation: {} {}", openvpn_path, args.join(" "));
+    
+    // Async so a cancelled connect stops waiting on the password prompt
+    let output = tokio::process::Command::from(elevated_command(&openvpn_path, &args))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to start OpenVPN: {}", e))?;
+    
+    if !output.status.success() {
+        if is_elevation_cancelled(&output) {
+            return Err("Connection cancelled. Administrator privileges are required.".to_string());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("OpenVPN failed: {}", stderr.trim()));
+    }
+    
+    // --daemon returns once OpenVPN has forked, so wait for the tunnel to come up
+    for _ in 0..20 {
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        if let Some(state) = openvpn_management_command("state") {
+            if state.contains(",CONNECTED,") {
+                log::info!("OpenVPN connected with config: {}", config_path);
+                return Ok(VpnStatusInfo {
                     status: VpnStatus::Connected,
                     vpn_type: Some(VpnType::OpenVPN),
                     tunnel_name: Some("openvpn".to_string()),
+                    assigned_ip: None,
                     error: None,
-                };
+                });
             }
         }
     }
     
-    VpnStatusInfo {
+    if read_openvpn_pid().is_none() {
+        return Err("OpenVPN exited during startup. Check the configuration and system logs.".to_string());
+    }
+    
+    // The process is up but still negotiating; get_vpn_status will report when it connects
+    log::info!("OpenVPN started with config: {}, still connecting", config_path);
+    
+    Ok(VpnStatusInfo {
+        status: VpnStatus::Connecting,
+        vpn_type: Some(VpnType::OpenVPN),
+        tunnel_name: Some("openvpn".to_string()),
+        assigned_ip: None,
+        error: None,
+    })
+}
+
+#[cfg(unix)]
+async fn disconnect_openvpn() -> Result<VpnStatusInfo, String> {
+    // Ask our own instance to exit via the management interface
+    if openvpn_management_command("signal SIGTERM").is_some() {
+        log::info!("OpenVPN asked to exit via management interface");
+    } else if let Some(pid) = read_openvpn_pid() {
+        // Fall back to the PID we recorded, never a blanket kill of every openvpn
+        let pid = pid.to_string();
+        let output = elevated_command("kill", &["-TERM", &pid])
+            .output()
+            .map_err(|e| format!("Failed to stop OpenVPN: {}", e))?;
+        
+        if !output.status.success() {
+            if is_elevation_cancelled(&output) {
+                return Err("Disconnect cancelled. Administrator privileges are required.".to_string());
+            }
+            log::warn!("Failed to stop OpenVPN (pid {}): {}", pid, String::from_utf8_lossy(&output.stderr));
+        }
+    } else {
+        log::warn!("OpenVPN may not have been running");
+    }
+    private_files::remove_private(&openvpn_status_file());
+    
+    log::info!("OpenVPN disconnected");
+    
+    Ok(VpnStatusInfo {
         status: VpnStatus::Disconnected,
         vpn_type: None,
         tunnel_name: None,
+        assigned_ip: None,
         error: None,
+    })
+}
+
+/// True if a Content-Type says nothing useful about text vs binary
+fn is_ambiguous_content_type(content_type: &str) -> bool {
+    let content_type = content_type.trim();
+    content_type.is_empty()
+        || content_type.contains("octet-stream")
+        || content_type.contains("binary/")
+        || content_type.contains("unknown")
+}
+
+/// Ask for the Content-Type with a HEAD request
+async fn head_content_type(client: &reqwest::Client, url: &str, headers: &[(String, String)]) -> Option<String> {
+    let mut request = client.head(url);
+    for (key, value) in headers {
+        request = request.header(key, value);
+    }
+    let response = request.send().await.ok()?;
+    response.headers()
+        .get(reqwest::header::CONTENT_TYPE)?
+        .to_str()
+        .ok()
+        .map(|s| s.to_lowercase())
+}
+
+/// Guess text vs binary from the first bytes. `Some(true)` means binary.
+fn sniff_is_binary(bytes: &[u8]) -> Option<bool> {
+    let text = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let start = text.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(text.len());
+    let text = &text[start..];
+    
+    const TEXT_PREFIXES: &[&[u8]] = &[b"#EXTM3U", b"<?xml", b"<tv", b"<MPD", b"{", b"["];
+    if TEXT_PREFIXES.iter().any(|prefix| text.starts_with(prefix)) {
+        return Some(false);
+    }
+    
+    // MPEG-TS repeats its sync byte every 188 bytes
+    let is_mpegts = bytes.first() == Some(&0x47) && bytes.get(188).map_or(true, |b| *b == 0x47);
+    let is_mp4 = bytes.get(4..8) == Some(b"ftyp");
+    const BINARY_MAGIC: &[&[u8]] = &[
+        b"\x1A\x45\xDF\xA3", // Matroska/WebM
+        b"ID3",
+        b"\x89PNG",
+        b"\xFF\xD8\xFF",     // JPEG
+        b"GIF8",
+        b"\x1F\x8B",          // gzip
+        b"RIFF",
+        b"OggS",
+        b"fLaC",
+    ];
+    if is_mpegts || is_mp4 || BINARY_MAGIC.iter().any(|magic| bytes.starts_with(magic)) {
+        return Some(true);
+    }
+    
+    None
+}
+
+/// Decompressed size past which a gzip body is returned as-is
+const MAX_GUNZIP_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Text inside a gzip body (`.gz` playlists and EPGs served as octet-stream), or
+/// `None` if it isn't gzip, doesn't decompress or isn't UTF-8 text
+fn gunzip_text(bytes: &[u8]) -> Option<String> {
+    use std::io::Read;
+    if !bytes.starts_with(b"\x1F\x8B") {
+        return None;
     }
+    let mut text = Vec::new();
+    flate2::read::MultiGzDecoder::new(bytes)
+        .take(MAX_GUNZIP_BYTES + 1)
+        .read_to_end(&mut text)
+        .map_err(|e| log::warn!("Body looks like gzip but failed to decompress: {}", e))
+        .ok()?;
+    if text.len() as u64 > MAX_GUNZIP_BYTES {
+        log::warn!("Decompressed body exceeds {} bytes, returning it compressed", MAX_GUNZIP_BYTES);
+        return None;
+    }
+    String::from_utf8(text).ok()
+}
+
+/// Longest `Retry-After` we are willing to sleep for between attempts
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+/// Backoff between retries when the server doesn't say how long to wait
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+}
+
+/// Parse `Retry-After` as delay-seconds or an HTTP-date, capped to `MAX_RETRY_AFTER_SECS`
+fn retry_after_delay(headers: &reqwest::header::HeaderMap) -> Option<std::time::Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    let secs = match value.parse::<u64>() {
+        Ok(secs) => secs,
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_secs() as i64;
+            date.timestamp().saturating_sub(now).max(0) as u64
+        }
+    };
+    Some(std::time::Duration::from_secs(secs.min(MAX_RETRY_AFTER_SECS)))
 }
 
 /// Universal HTTP fetch command to bypass CORS
 /// This command acts as a proxy between the frontend and remote APIs
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn http_fetch(
+    shared_client: State<'_, http_client::SharedHttpClient>,
+    settings: State<'_, settings::SettingsStore>,
+    sessions: State<'_, sessions::ActiveSessions>,
     url: String,
     method: Option<String>,
     headers: Option<Vec<(String, String)>>,
     body: Option<String>,
-) -> Result<HttpResponse, String> {
+    with_timing: Option<bool>,
+    verify_content_type: Option<bool>,
+    body_base64: Option<String>,
+    http_version: Option<String>,
+    retries: Option<u32>,
+    use_vpn_dns: Option<bool>,
+    pinned_cert_sha256: Option<String>,
+    if_range: Option<String>,
+) -> Result<HttpResponse, CommandError> {
+    // Fail early with a readable error instead of reqwest's parse error
+    let url = urls::normalize_url(url)?;
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("Unsupported URL scheme in {}: only http and https can be fetched", url).into());
+    }
     log::info!("Fetching URL: {}", url);
     
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let vpn_dns = if use_vpn_dns.unwrap_or(false) {
+        let tunnels = sessions.tunnels.lock().await;
+        let mut names: Vec<&String> = tunnels.keys().collect();
+        names.sort();
+        let servers: Vec<std::net::IpAddr> = names.into_iter()
+            .flat_map(|name| tunnels[name].dns_servers.iter().copied())
+            .collect();
+        if servers.is_empty() {
+            return Err("use_vpn_dns requires a connected VPN with DNS servers".into());
+        }
+        Some(servers)
+    } else {
+        None
+    };
+    
+    let cert_pin = pinned_cert_sha256.as_deref().map(cert_pin::CertPin::parse).transpose()?;
+    if cert_pin.is_some() && !url.to_lowercase().starts_with("https://") {
+        return Err("pinned_cert_sha256 requires an https:// URL".into());
+    }
+    
+    let http_version = http_version.unwrap_or_else(|| "auto".to_string());
+    let client = match http_client::client_for_request(&settings.get(), &http_version, vpn_dns, cert_pin.as_ref())? {
+        Some(client) => client,
+        None => shared_client.get(),
+    };
     
     let method = method.unwrap_or_else(|| "GET".to_string());
     let mut request = match method.to_uppercase().as_str() {
@@ -505,26 +2071,103 @@ async fn http_fetch(
         "DELETE" => client.delete(&url),
         "PATCH" => client.patch(&url),
         "HEAD" => client.head(&url),
-        _ => return Err(format!("Unsupported HTTP method: {}", method)),
+        _ => return Err(format!("Unsupported HTTP method: {}", method).into()),
     };
 
-    // Add custom headers
-    if let Some(headers) = headers {
-        for (key, value) in headers {
-            request = request.header(key, value);
+    // Add custom headers, then the global rules' headers the request doesn't set itself
+    let mut headers = headers.unwrap_or_default();
+    let global = http_client::global_headers(&settings.get().header_rules, &url, &headers);
+    headers.extend(global);
+    for (key, value) in &headers {
+        request = request.header(key, value);
+    }
+
+    // `If-Range` takes the ETag or Last-Modified of the response the earlier bytes came from
+    let ranged = headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("range"));
+    if let Some(validator) = if_range.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        if !ranged {
+            return Err("if_range requires a Range header".into());
+        }
+        if validator.starts_with("W/") {
+            return Err("if_range needs a strong ETag or a Last-Modified date, not a weak ETag".into());
         }
+        request = request.header(reqwest::header::IF_RANGE, validator);
     }
 
-    // Add body for POST/PUT/PATCH
-    if let Some(body) = body {
+    // Add body for POST/PUT/PATCH; a base64 body is sent as raw bytes and wins over `body`
+    if let Some(encoded) = body_base64 {
+        let bytes = general_purpose::STANDARD.decode(encoded.trim())
+            .map_err(|e| format!("Invalid base64 body: {}", e))?;
+        request = request.body(bytes);
+    } else if let Some(body) = body {
         request = request.body(body);
     }
 
-    // Execute request
-    let response = request.send().await
-        .map_err(|e| format!("Request failed: {}", e))?;
+    // Execute request, retrying rate limits and connection failures up to `retries` times
+    let max_retries = retries.unwrap_or(0);
+    let mut attempt = 0;
+    let (response, started) = loop {
+        let attempt_request = request.try_clone()
+            .ok_or("Request body can't be sent more than once")?;
+        let started = std::time::Instant::now();
+        let backoff = std::time::Duration::from_millis(RETRY_BASE_DELAY_MS << attempt.min(6));
+        match attempt_request.send().await {
+            Ok(response) if attempt < max_retries && is_retryable_status(response.status()) => {
+                let wait = retry_after_delay(response.headers()).unwrap_or(backoff);
+                console::record(ConsoleLevel::Warn, "http", format!(
+                    "{} {} returned {}, retrying in {}ms ({}/{})",
+                    method, url, response.status(), wait.as_millis(), attempt + 1, max_retries
+                ));
+                tokio::time::sleep(wait).await;
+            }
+            Ok(response) => break (response, started),
+            // A pin mismatch fails the handshake like a connect error, but retrying won't help
+            Err(_) if cert_pin.as_ref().is_some_and(|pin| pin.mismatch().is_some()) => {
+                let pin = cert_pin.as_ref().expect("checked above");
+                let actual = pin.mismatch().unwrap_or_default();
+                let host = reqwest::Url::parse(&url).ok()
+                    .and_then(|u| u.host_str().map(|h| h.to_string()))
+                    .unwrap_or_default();
+                let message = format!(
+                    "Certificate for {} does not match the pinned fingerprint (expected {}, got {})",
+                    host, pin.expected, actual
+                );
+                console::record(ConsoleLevel::Error, "http", message.clone());
+                return Err(CommandError::CertPinMismatch {
+                    host,
+                    expected: pin.expected.clone(),
+                    actual,
+                    message,
+                });
+            }
+            Err(e) if attempt < max_retries && (e.is_connect() || e.is_timeout()) => {
+                console::record(ConsoleLevel::Warn, "http", format!(
+                    "{} {} failed: {}, retrying in {}ms ({}/{})",
+                    method, url, e, backoff.as_millis(), attempt + 1, max_retries
+                ));
+                tokio::time::sleep(backoff).await;
+            }
+            Err(e) => {
+                console::record(ConsoleLevel::Warn, "http", format!("{} {} failed: {}", method, url, e));
+                return Err(if attempt > 0 {
+                    format!("Request failed after {} attempts: {}", attempt + 1, e).into()
+                } else {
+                    format!("Request failed: {}", e).into()
+                });
+            }
+        }
+        attempt += 1;
+    };
+    let first_byte = started.elapsed();
 
     let status = response.status().as_u16();
+    let retry_after_ms = is_retryable_status(response.status())
+        .then(|| retry_after_delay(response.headers()))
+        .flatten()
+        .map(|delay| delay.as_millis() as u64);
+    let negotiated_version = format!("{:?}", response.version());
+    let range_applied = (ranged && response.status().is_success())
+        .then(|| response.status() == reqwest::StatusCode::PARTIAL_CONTENT);
     
     // Extract headers
     let mut response_headers = std::collections::HashMap::new();
@@ -537,12 +2180,32 @@ async fn http_fetch(
     // Get response body as bytes
     let bytes = response.bytes().await
         .map_err(|e| format!("Failed to read response body: {}", e))?;
+    let total = started.elapsed();
+
+    // reqwest doesn't expose DNS/connect/TLS splits, so report what we can measure
+    let timing = with_timing.unwrap_or(false).then(|| ResponseTiming {
+        first_byte_ms: first_byte.as_millis() as u64,
+        body_ms: (total - first_byte).as_millis() as u64,
+        total_ms: total.as_millis() as u64,
+    });
 
     // Determine if content is binary or text
-    let content_type = response_headers.get("content-type")
+    let mut content_type = response_headers.get("content-type")
         .map(|s| s.to_lowercase())
         .unwrap_or_default();
     
+    // Some servers only send a meaningful Content-Type on HEAD; only pay the
+    // extra round trip when the GET's type doesn't tell us anything
+    let verify_content_type = verify_content_type.unwrap_or(false);
+    if verify_content_type && method.eq_ignore_ascii_case("GET") && is_ambiguous_content_type(&content_type) {
+        if let Some(head_type) = head_content_type(&client, &url, &headers).await {
+            if !is_ambiguous_content_type(&head_type) {
+                log::info!("Using Content-Type '{}' from HEAD instead of '{}'", head_type, content_type);
+                content_type = head_type;
+            }
+        }
+    }
+    
     // Consider text formats (JSON, XML, plain text, M3U playlists)
     let is_text = content_type.contains("json") ||
                   content_type.contains("xml") ||
@@ -552,66 +2215,68 @@ async fn http_fetch(
                   content_type.contains("m3u");
     
     // Consider binary formats (video, audio, images, etc.)
-    let is_binary = !is_text && (
+    let mut is_binary = !is_text && (
                     content_type.contains("video/") ||
                     content_type.contains("audio/") ||
                     content_type.contains("image/") ||
                     content_type.contains("octet-stream"));
     
+    // Still no reliable type: let the content decide
+    if verify_content_type && is_ambiguous_content_type(&content_type) {
+        if let Some(sniffed) = sniff_is_binary(&bytes) {
+            is_binary = sniffed;
+        }
+    }
+    
+    // Servers often send .gz playlists/EPGs as octet-stream without Content-Encoding;
+    // the gzip magic decides, decompression runs off the async workers
+    let gunzipped = match bytes.starts_with(b"\x1F\x8B") {
+        true => {
+            let compressed = bytes.clone();
+            tauri::async_runtime::spawn_blocking(move || gunzip_text(&compressed)).await.ok().flatten()
+        }
+        false => None,
+    };
+    let gzip_decoded = gunzipped.is_some();
+    if gzip_decoded {
+        is_binary = false;
+    }
+    
     // For binary data or when we can't decode as UTF-8, use base64 encoding
-    let body = if is_binary || std::str::from_utf8(&bytes).is_err() {
+    let body = if let Some(text) = gunzipped {
+        text
+    } else if is_binary || std::str::from_utf8(&bytes).is_err() {
         general_purpose::STANDARD.encode(&bytes)
     } else {
         String::from_utf8_lossy(&bytes).to_string()
     };
 
-    log::info!("Request completed with status: {}, binary: {}, size: {} bytes", status, is_binary, bytes.len());
+    log::info!(
+        "Request completed with status: {}, binary: {}, size: {} bytes{}",
+        status, is_binary, bytes.len(), if gzip_decoded { " (gzip decoded)" } else { "" }
+    );
 
     Ok(HttpResponse {
         body,
         status,
         headers: response_headers,
+        http_version: negotiated_version,
         is_binary: Some(is_binary),
+        timing,
+        retry_after_ms,
+        gzip_decoded: gzip_decoded.then_some(true),
+        range_applied,
     })
 }
 
-/// Placeholder command for starting RTSP/RTMP proxy
-/// This will be implemented when MediaMTX/FFmpeg sidecar is added
-#[tauri::command]
-async fn start_stream_proxy(
-    source_url: String,
-    output_port: u16,
-    protocol: String,
-) -> Result<serde_json::Value, String> {
-    log::info!(
-        "Starting {} proxy for {} on port {}",
-        protocol,
-        source_url,
-        output_port
-    );
-    
-    // TODO: Implement MediaMTX/FFmpeg sidecar launch
-    // For now, return a placeholder response
-    Ok(serde_json::json!({
-        "hlsUrl": format!("http://localhost:{}/stream/index.m3u8", output_port),
-        "status": "pending_implementation"
-    }))
-}
-
-/// Placeholder command for stopping RTSP/RTMP proxy
-#[tauri::command]
-async fn stop_stream_proxy(source_url: String) -> Result<(), String> {
-    log::info!("Stopping proxy for {}", source_url);
-    // TODO: Implement proxy stop
-    Ok(())
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(sessions::ActiveSessions::default())
+        .manage(local_tls::LocalCert::default())
         .setup(|app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -621,6 +2286,12 @@ pub fn run() {
                 )?;
             }
             
+            temp_dirs::sweep_orphans();
+
+            let settings = settings::SettingsStore::load(app.handle());
+            app.manage(http_client::SharedHttpClient::new(&settings.get())?);
+            app.manage(settings);
+
             // Log app start
             log::info!("ClaudeTV started successfully!");
             
@@ -629,14 +2300,65 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             http_fetch,
-            start_stream_proxy,
-            stop_stream_proxy,
+            urls::normalize_url,
+            http_client::get_system_proxy,
+            http_client::set_respect_system_proxy,
+            http_client::get_global_headers,
+            http_client::set_global_headers,
+            stream_proxy::start_stream_proxy,
+            stream_proxy::stop_stream_proxy,
+            stream_proxy::get_proxy_logs,
+            stream_proxy::test_proxy_endpoint,
+            stream_proxy::set_proxy_cors_origin,
+            stream_proxy::get_server_info,
+            local_tls::get_local_cert,
+            local_tls::regenerate_local_cert,
+            local_tls::trust_local_cert,
+            recording::start_recording,
+            recording::stop_recording,
+            console::subscribe_console,
+            console::unsubscribe_console,
+            ffmpeg::list_hwaccels,
+            ffmpeg_jobs::run_ffmpeg,
+            ffmpeg_jobs::stop_ffmpeg,
+            diagnostics::collect_diagnostics,
+            backup::export_state,
+            backup::import_state,
+            download::download_file,
+            playlist::parse_m3u,
+            playlist::merge_playlists,
+            playlist::channels_to_m3u,
+            hls::parse_hls_master,
+            logos::fetch_logos,
+            sniff::sniff_media,
+            epg::parse_xmltv,
+            epg::match_epg_to_channels,
             detect_vpn_clients,
             parse_vpn_config,
+            normalize_wireguard_config,
             connect_vpn,
+            connect_vpn_from_content,
             disconnect_vpn,
-            get_vpn_status
+            reconnect_vpn,
+            cancel_connect,
+            cleanup_orphaned_tunnels,
+            check_elevation_available,
+            routing::check_routing_conflict,
+            throughput::measure_throughput,
+            get_vpn_status,
+            get_wireguard_peers,
+            get_openvpn_stats
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                let sessions = app.state::<sessions::ActiveSessions>();
+                tauri::async_runtime::block_on(async {
+                    remove_ephemeral_tunnels(&sessions).await;
+                    stream_proxy::stop_all_proxies(&sessions).await;
+                    temp_dirs::release_all(&sessions).await;
+                });
+            }
+        });
 }
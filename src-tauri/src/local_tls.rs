@@ -0,0 +1,216 @@
+// ========================================
+// Local HTTPS Certificate
+// ========================================
+//
+// Self-signed certificate for `localhost` / `127.0.0.1`, so the stream proxy can be
+// served over HTTPS to pages that block mixed content. Browsers only accept it once
+// it is trusted: `trust_local_cert` adds it to the OS store with elevation. Firefox
+// and most Linux browsers keep their own store (NSS) and need it imported there.
+
+use chrono::Datelike;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager, State};
+use tokio_rustls::TlsAcceptor;
+
+const CERT_FILE: &str = "localhost-cert.pem";
+const KEY_FILE: &str = "localhost-key.pem";
+
+/// Apple rejects TLS server certificates valid for longer than 825 days
+const CERT_VALIDITY_DAYS: i64 = 820;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocalCertInfo {
+    pub cert_path: String,
+    /// SHA-256 of the certificate, to compare with what the browser shows
+    pub sha256: String,
+}
+
+/// TLS acceptor for the local certificate, loaded on first use and managed as Tauri state
+#[derive(Default)]
+pub struct LocalCert(Mutex<Option<TlsAcceptor>>);
+
+impl LocalCert {
+    /// Acceptor for the stored certificate, generating one the first time
+    pub fn acceptor(&self, app: &AppHandle) -> Result<TlsAcceptor, String> {
+        let mut acceptor = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(acceptor) = acceptor.as_ref() {
+            return Ok(acceptor.clone());
+        }
+        let dir = tls_dir(app)?;
+        let loaded = match load(&dir) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                log::info!("Generating local HTTPS certificate ({})", e);
+                generate(&dir)?
+            }
+        };
+        let created = server_acceptor(loaded)?;
+        *acceptor = Some(created.clone());
+        Ok(created)
+    }
+
+    fn reset(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+fn tls_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_data_dir()
+        .map(|dir| dir.join("tls"))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+type CertAndKey = (CertificateDer<'static>, PrivateKeyDer<'static>);
+
+fn load(dir: &std::path::Path) -> Result<CertAndKey, String> {
+    let cert = CertificateDer::from_pem_file(dir.join(CERT_FILE))
+        .map_err(|e| format!("no usable certificate: {}", e))?;
+    let key = PrivateKeyDer::from_pem_file(dir.join(KEY_FILE))
+        .map_err(|e| format!("no usable key: {}", e))?;
+    Ok((cert, key))
+}
+
+/// Create and store a new certificate and key, valid from today
+fn generate(dir: &std::path::Path) -> Result<CertAndKey, String> {
+    let today = chrono::DateTime::<chrono::Utc>::from(std::time::SystemTime::now());
+    let expires = today + chrono::Duration::days(CERT_VALIDITY_DAYS);
+    let ymd = |d: chrono::DateTime<chrono::Utc>| rcgen::date_time_ymd(d.year(), d.month() as u8, d.day() as u8);
+
+    let mut params = rcgen::CertificateParams::new(vec!["localhost".to_string(), "127.0.0.1".to_string()])
+        .map_err(|e| format!("Failed to prepare certificate: {}", e))?;
+    params.distinguished_name = rcgen::DistinguishedName::new();
+    params.distinguished_name.push(rcgen::DnType::CommonName, "ClaudeTV localhost");
+    params.distinguished_name.push(rcgen::DnType::OrganizationName, "ClaudeTV");
+    params.not_before = ymd(today);
+    params.not_after = ymd(expires);
+    params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::ServerAuth];
+    let key_pair = rcgen::KeyPair::generate()
+        .map_err(|e| format!("Failed to generate key: {}", e))?;
+    let cert = params.self_signed(&key_pair)
+        .map_err(|e| format!("Failed to sign certificate: {}", e))?;
+
+    crate::private_files::create_private_dir(dir)?;
+    std::fs::write(dir.join(CERT_FILE), cert.pem())
+        .map_err(|e| format!("Failed to save certificate: {}", e))?;
+    crate::private_files::write_private(&dir.join(KEY_FILE), key_pair.serialize_pem().as_bytes())?;
+
+    let key = PrivateKeyDer::try_from(key_pair.serialize_der())
+        .map_err(|e| format!("Failed to load generated key: {}", e))?;
+    Ok((cert.der().clone(), key))
+}
+
+fn server_acceptor((cert, key): CertAndKey) -> Result<TlsAcceptor, String> {
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .map_err(|e| format!("Invalid local certificate: {}", e))?;
+    // The embedded server only speaks HTTP/1.1
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// The stored local certificate, for clients that connect to the proxy themselves
+pub fn certificate(app: &AppHandle) -> Result<reqwest::Certificate, String> {
+    let path = tls_dir(app)?.join(CERT_FILE);
+    let pem = std::fs::read(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    reqwest::Certificate::from_pem(&pem)
+        .map_err(|e| format!("Invalid local certificate: {}", e))
+}
+
+fn cert_info(app: &AppHandle) -> Result<LocalCertInfo, String> {
+    let path = tls_dir(app)?.join(CERT_FILE);
+    let cert = CertificateDer::from_pem_file(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(LocalCertInfo {
+        cert_path: path.to_string_lossy().to_string(),
+        sha256: Sha256::digest(cert.as_ref()).iter().map(|b| format!("{:02x}", b)).collect(),
+    })
+}
+
+/// Path and fingerprint of the local certificate, generating it if there is none yet
+#[tauri::command]
+pub fn get_local_cert(app: AppHandle, local_cert: State<'_, LocalCert>) -> Result<LocalCertInfo, String> {
+    local_cert.acceptor(&app)?;
+    cert_info(&app)
+}
+
+/// Replace the local certificate. Proxies started afterwards use the new one; a cert
+/// trusted with `trust_local_cert` has to be trusted again.
+#[tauri::command]
+pub fn regenerate_local_cert(app: AppHandle, local_cert: State<'_, LocalCert>) -> Result<LocalCertInfo, String> {
+    generate(&tls_dir(&app)?)?;
+    local_cert.reset();
+    local_cert.acceptor(&app)?;
+    let info = cert_info(&app)?;
+    log::info!("Regenerated local HTTPS certificate {}", info.sha256);
+    Ok(info)
+}
+
+/// Add the local certificate to the OS trust store, prompting for admin rights
+#[tauri::command]
+pub async fn trust_local_cert(app: AppHandle, local_cert: State<'_, LocalCert>) -> Result<LocalCertInfo, String> {
+    local_cert.acceptor(&app)?;
+    let info = cert_info(&app)?;
+    log::info!("Trusting local HTTPS certificate {}", info.cert_path);
+    install_trusted(&info.cert_path).await?;
+    Ok(info)
+}
+
+#[cfg(windows)]
+async fn install_trusted(cert_path: &str) -> Result<(), String> {
+    let ps_command = format!(
+        r#"Start-Process -FilePath 'certutil.exe' -ArgumentList '-addstore','-f','Root','"{}"' -Verb RunAs -Wait -WindowStyle Hidden"#,
+        cert_path.replace('\'', "''")
+    );
+    let output = tokio::process::Command::new("powershell")
+        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &ps_command])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to start certutil: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("canceled") || stderr.contains("cancelled") {
+            return Err("Cancelled. Administrator privileges are required to trust the certificate.".to_string());
+        }
+        return Err(format!("certutil failed: {}", stderr.trim()));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn install_trusted(cert_path: &str) -> Result<(), String> {
+    let (program, args): (&str, Vec<&str>) = if cfg!(target_os = "macos") {
+        ("security", vec!["add-trusted-cert", "-d", "-r", "trustRoot", "-k", "/Library/Keychains/System.keychain", cert_path])
+    } else {
+        // Debian/Ubuntu layout; update-ca-certificates only picks up `.crt` files
+        let script = format!(
+            "cp {} /usr/local/share/ca-certificates/claudetv-localhost.crt && update-ca-certificates",
+            crate::shell_quote(cert_path)
+        );
+        return run_elevated("sh", &["-c", &script]).await;
+    };
+    run_elevated(program, &args).await
+}
+
+#[cfg(unix)]
+async fn run_elevated(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = tokio::process::Command::from(crate::elevated_command(program, args))
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if crate::is_elevation_cancelled(&output) {
+        return Err("Cancelled. Administrator privileges are required to trust the certificate.".to_string());
+    }
+    if !output.status.success() {
+        return Err(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// ========================================
+// Channel Logo Cache
+// ========================================
+
+use crate::http_client::SharedHttpClient;
+use base64::{engine::general_purpose, Engine as _};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+
+const DEFAULT_CONCURRENCY: usize = 8;
+const MAX_CONCURRENCY: usize = 32;
+
+/// Anything bigger isn't a logo
+const MAX_LOGO_BYTES: usize = 2 * 1024 * 1024;
+
+const LOGO_TIMEOUT_SECS: u64 = 15;
+
+/// Image type from the leading bytes, for servers that send no or a generic Content-Type
+fn sniff_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    let head = &bytes[..bytes.len().min(512)];
+    if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if head.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP") {
+        Some("image/webp")
+    } else if head.starts_with(b"\x00\x00\x01\x00") {
+        Some("image/x-icon")
+    } else if head.starts_with(b"BM") {
+        Some("image/bmp")
+    } else if String::from_utf8_lossy(head).to_lowercase().contains("<svg") {
+        Some("image/svg+xml")
+    } else {
+        None
+    }
+}
+
+fn cache_path(dir: &Path, url: &str) -> PathBuf {
+    let hash: String = Sha256::digest(url.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+    dir.join(format!("{}.datauri", hash))
+}
+
+async fn download_logo(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let mut response = client.get(url)
+        .timeout(std::time::Duration::from_secs(LOGO_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Status {}", response.status()));
+    }
+    if response.content_length().is_some_and(|len| len as usize > MAX_LOGO_BYTES) {
+        return Err("Image too large".to_string());
+    }
+    let declared = response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or_default().trim().to_lowercase())
+        .filter(|v| v.starts_with("image/"));
+    // Content-Length can be missing or wrong, so the cap is enforced while reading
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await
+        .map_err(|e| format!("Failed to read body: {}", e))?
+    {
+        if bytes.len() + chunk.len() > MAX_LOGO_BYTES {
+            return Err("Image too large".to_string());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    let mime = declared
+        .or_else(|| sniff_image_mime(&bytes).map(|m| m.to_string()))
+        .ok_or("Not an image")?;
+    Ok(format!("data:{};base64,{}", mime, general_purpose::STANDARD.encode(&bytes)))
+}
+
+/// Logo from the disk cache, or downloaded and cached
+async fn resolve_logo(client: &reqwest::Client, dir: &Path, url: &str) -> Option<String> {
+    let path = cache_path(dir, url);
+    if let Ok(cached) = tokio::fs::read_to_string(&path).await {
+        return Some(cached);
+    }
+    match download_logo(client, url).await {
+        Ok(data_url) => {
+            // Write then rename so an interrupted write never leaves a truncated entry
+            let partial = path.with_extension("part");
+            let cached = match tokio::fs::write(&partial, &data_url).await {
+                Ok(()) => tokio::fs::rename(&partial, &path).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = cached {
+                log::warn!("Failed to cache logo {}: {}", url, e);
+            }
+            Some(data_url)
+        }
+        Err(e) => {
+            log::debug!("Logo {} unavailable: {}", url, e);
+            None
+        }
+    }
+}
+
+/// Resolve logo URLs to `data:` URLs through the shared client, `concurrency` at a time.
+/// Results are cached on disk by URL; entries that fail to load are null, in input order.
+#[tauri::command]
+pub async fn fetch_logos(
+    app: AppHandle,
+    shared_client: State<'_, SharedHttpClient>,
+    urls: Vec<String>,
+    concurrency: Option<usize>,
+) -> Result<Vec<Option<String>>, String> {
+    let dir = app.path().app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?
+        .join("logos");
+    tokio::fs::create_dir_all(&dir).await
+        .map_err(|e| format!("Failed to create logo cache: {}", e))?;
+
+    let concurrency = concurrency.unwrap_or(DEFAULT_CONCURRENCY).clamp(1, MAX_CONCURRENCY);
+    let permits = Arc::new(tokio::sync::Semaphore::new(concurrency));
+    let dir = Arc::new(dir);
+    let client = shared_client.get();
+
+    let tasks: Vec<_> = urls.iter()
+        .map(|url| {
+            let (client, dir, permits, url) = (client.clone(), dir.clone(), permits.clone(), url.clone());
+            tauri::async_runtime::spawn(async move {
+                let is_http = url.starts_with("http://") || url.starts_with("https://");
+                if !is_http {
+                    return None;
+                }
+                let _permit = permits.acquire().await.ok()?;
+                resolve_logo(&client, &dir, &url).await
+            })
+        })
+        .collect();
+
+    let mut logos = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        logos.push(task.await.ok().flatten());
+    }
+
+    log::info!(
+        "Resolved {}/{} logos (concurrency {})",
+        logos.iter().filter(|l| l.is_some()).count(),
+        logos.len(),
+        concurrency
+    );
+    Ok(logos)
+}
@@ -0,0 +1,383 @@
+// ========================================
+// M3U Playlists
+// ========================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Channel {
+    pub name: String,
+    pub url: String,
+    pub group: Option<String>,
+    pub logo: Option<String>,
+    pub tvg_id: Option<String>,
+    pub tvg_name: Option<String>,
+    /// Duration from `#EXTINF:<duration>`, -1 for live streams
+    pub duration: Option<i64>,
+    /// `tvg-shift` in hours (e.g. 1, -0.5) to align guide times with this channel
+    #[serde(default)]
+    pub tvg_shift: Option<f32>,
+    /// HTTP headers from `#EXTVLCOPT:http-*` lines, e.g. User-Agent and Referer
+    #[serde(default)]
+    pub http_headers: HashMap<String, String>,
+    /// Raw `#KODIPROP` properties, e.g. `inputstream.adaptive.license_key`
+    #[serde(default)]
+    pub props: HashMap<String, String>,
+}
+
+/// Which fields identify "the same channel" when merging playlists
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum DedupKey {
+    #[default]
+    Url,
+    TvgIdName,
+}
+
+/// A dropped duplicate whose group differed from the channel that was kept
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupCollision {
+    pub name: String,
+    pub kept_group: Option<String>,
+    pub dropped_group: Option<String>,
+    /// Index of the source playlist the duplicate came from
+    pub source_index: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergedPlaylist {
+    pub channels: Vec<Channel>,
+    pub duplicates_dropped: usize,
+    pub collisions: Vec<GroupCollision>,
+}
+
+/// Split `#EXTINF` attributes (`key="value"` pairs) from the trailing display name.
+/// The name starts after the first comma outside quotes, so commas inside values are fine.
+fn parse_extinf(line: &str) -> (Option<i64>, HashMap<String, String>, String) {
+    let rest = line.trim_start_matches("#EXTINF:");
+
+    let mut in_quotes = false;
+    let split = rest.char_indices()
+        .find(|&(_, c)| {
+            if c == '"' {
+                in_quotes = !in_quotes;
+            }
+            c == ',' && !in_quotes
+        })
+        .map(|(i, _)| i);
+    let (info, name) = match split {
+        Some(i) => (&rest[..i], rest[i + 1..].trim()),
+        None => (rest, ""),
+    };
+
+    let duration = info.split_whitespace().next().and_then(|d| d.parse().ok());
+
+    let mut attributes = HashMap::new();
+    let mut remaining = info;
+    while let Some(eq) = remaining.find("=\"") {
+        let key = remaining[..eq].rsplit(char::is_whitespace).next().unwrap_or_default();
+        let value_start = eq + 2;
+        let Some(value_len) = remaining[value_start..].find('"') else {
+            break;
+        };
+        if !key.is_empty() {
+            attributes.insert(
+                key.to_lowercase(),
+                remaining[value_start..value_start + value_len].to_string(),
+            );
+        }
+        remaining = &remaining[value_start + value_len + 1..];
+    }
+
+    (duration, attributes, name.to_string())
+}
+
+/// Map a VLC `http-*` option to the HTTP header it sets
+fn vlc_option_header(option: &str) -> Option<String> {
+    let name = option.strip_prefix("http-")?;
+    let header = match name {
+        "user-agent" => "User-Agent".to_string(),
+        // VLC spells it correctly, HTTP doesn't
+        "referrer" | "referer" => "Referer".to_string(),
+        _ => name.split('-')
+            .map(|part| {
+                let mut chars = part.chars();
+                chars.next()
+                    .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join("-"),
+    };
+    Some(header)
+}
+
+/// `tvg-shift` values look like "+1", "-0.5" or "2"
+fn parse_tvg_shift(value: &str) -> Option<f32> {
+    let value = value.trim();
+    value.strip_prefix('+').unwrap_or(value).parse().ok().filter(|v: &f32| v.is_finite())
+}
+
+/// Parse an M3U/M3U8 playlist into channels
+pub fn parse_m3u_content(content: &str) -> Vec<Channel> {
+    let mut channels = Vec::new();
+    let mut current: Option<Channel> = None;
+    // `#EXTM3U tvg-shift="..."` applies to channels that don't set their own
+    let mut default_shift = None;
+    // `#EXTGRP:<group>` groups the following entries until the next #EXTGRP,
+    // for those without their own group-title
+    let mut extgrp: Option<String> = None;
+    // Options may appear before or after #EXTINF, so collect them until the URL
+    let mut http_headers = HashMap::new();
+    let mut props = HashMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix("#EXTM3U") {
+            let (_, attrs, _) = parse_extinf(header);
+            default_shift = attrs.get("tvg-shift").and_then(|v| parse_tvg_shift(v));
+            continue;
+        }
+
+        if line.starts_with("#EXTINF:") {
+            let (duration, mut attrs, name) = parse_extinf(line);
+            let non_empty = |v: Option<String>| v.filter(|s| !s.is_empty());
+            current = Some(Channel {
+                name: if name.is_empty() { format!("Channel {}", channels.len() + 1) } else { name },
+                url: String::new(),
+                group: non_empty(attrs.remove("group-title")),
+                logo: non_empty(attrs.remove("tvg-logo").or_else(|| attrs.remove("logo"))),
+                tvg_id: non_empty(attrs.remove("tvg-id")),
+                tvg_name: non_empty(attrs.remove("tvg-name")),
+                duration,
+                tvg_shift: attrs.remove("tvg-shift")
+                    .and_then(|v| parse_tvg_shift(&v))
+                    .or(default_shift),
+                http_headers: HashMap::new(),
+                props: HashMap::new(),
+            });
+            continue;
+        }
+
+        if let Some(group) = line.strip_prefix("#EXTGRP:") {
+            let group = group.trim();
+            extgrp = (!group.is_empty()).then(|| group.to_string());
+            continue;
+        }
+
+        if let Some(option) = line.strip_prefix("#EXTVLCOPT:") {
+            if let Some((key, value)) = option.split_once('=') {
+                if let Some(header) = vlc_option_header(key.trim()) {
+                    http_headers.insert(header, value.trim().to_string());
+                }
+            }
+            continue;
+        }
+
+        if let Some(prop) = line.strip_prefix("#KODIPROP:") {
+            if let Some((key, value)) = prop.split_once('=') {
+                props.insert(key.trim().to_string(), value.trim().to_string());
+            }
+            continue;
+        }
+
+        // Other directives
+        if line.starts_with('#') {
+            continue;
+        }
+
+        // Stream URL completes the pending entry
+        if let Some(mut channel) = current.take() {
+            channel.url = line.to_string();
+            if channel.group.is_none() {
+                channel.group = extgrp.clone();
+            }
+            channel.http_headers = std::mem::take(&mut http_headers);
+            channel.props = std::mem::take(&mut props);
+            channels.push(channel);
+        } else {
+            http_headers.clear();
+            props.clear();
+        }
+    }
+
+    channels
+}
+
+/// Parse an M3U playlist and return its channels, off the main thread
+#[tauri::command]
+pub async fn parse_m3u(content: String) -> Result<Vec<Channel>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let channels = parse_m3u_content(&content);
+        log::info!("Parsed playlist: {} channels ({} bytes)", channels.len(), content.len());
+        channels
+    })
+    .await
+    .map_err(|e| format!("Playlist parsing failed: {}", e))
+}
+
+/// Inverse of `vlc_option_header`: `Referer` -> `http-referrer`
+fn header_vlc_option(header: &str) -> String {
+    match header.to_lowercase().as_str() {
+        "referer" => "http-referrer".to_string(),
+        name => format!("http-{}", name),
+    }
+}
+
+/// M3U has no escaping, so drop characters that would break the line structure
+fn m3u_attr(value: &str) -> String {
+    value.chars().filter(|&c| c != '"' && c != '\r' && c != '\n').collect()
+}
+
+/// Serialize channels into an M3U playlist that `parse_m3u_content` reads back unchanged
+pub fn channels_to_m3u_content(channels: &[Channel]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+
+    for channel in channels {
+        out.push_str(&format!("#EXTINF:{}", channel.duration.unwrap_or(-1)));
+        let attributes = [
+            ("tvg-id", &channel.tvg_id),
+            ("tvg-name", &channel.tvg_name),
+            ("tvg-logo", &channel.logo),
+            ("group-title", &channel.group),
+        ];
+        for (key, value) in attributes {
+            if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+                out.push_str(&format!(" {}=\"{}\"", key, m3u_attr(value)));
+            }
+        }
+        if let Some(shift) = channel.tvg_shift {
+            out.push_str(&format!(" tvg-shift=\"{}\"", shift));
+        }
+        out.push_str(&format!(",{}\n", channel.name.replace(['\r', '\n'], " ")));
+
+        // Sorted so the output is stable across runs
+        let mut headers: Vec<_> = channel.http_headers.iter().collect();
+        headers.sort();
+        for (header, value) in headers {
+            out.push_str(&format!("#EXTVLCOPT:{}={}\n", header_vlc_option(header), m3u_attr(value)));
+        }
+        let mut props: Vec<_> = channel.props.iter().collect();
+        props.sort();
+        for (key, value) in props {
+            out.push_str(&format!("#KODIPROP:{}={}\n", key, m3u_attr(value)));
+        }
+
+        out.push_str(channel.url.trim());
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Export channels as an `#EXTM3U` playlist, the writer half of `parse_m3u`
+#[tauri::command]
+pub fn channels_to_m3u(channels: Vec<Channel>) -> String {
+    log::info!("Exporting {} channels to M3U", channels.len());
+    channels_to_m3u_content(&channels)
+}
+
+fn dedup_key(channel: &Channel, key: DedupKey) -> String {
+    match key {
+        DedupKey::Url => channel.url.clone(),
+        DedupKey::TvgIdName => format!(
+            "{}|{}",
+            channel.tvg_id.as_deref().unwrap_or_default().to_lowercase(),
+            channel.name.trim().to_lowercase()
+        ),
+    }
+}
+
+/// Merge several playlists (as M3U content) into one channel list.
+/// The first occurrence of a channel wins; later duplicates are dropped and counted.
+#[tauri::command]
+pub async fn merge_playlists(sources: Vec<String>, dedup_by: Option<DedupKey>) -> Result<MergedPlaylist, String> {
+    let key = dedup_by.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || merge_playlist_sources(&sources, key))
+        .await
+        .map_err(|e| format!("Playlist merge failed: {}", e))
+}
+
+fn merge_playlist_sources(sources: &[String], key: DedupKey) -> MergedPlaylist {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut channels: Vec<Channel> = Vec::new();
+    let mut duplicates_dropped = 0;
+    let mut collisions = Vec::new();
+
+    for (source_index, source) in sources.iter().enumerate() {
+        for channel in parse_m3u_content(source) {
+            let id = dedup_key(&channel, key);
+            match seen.get(&id) {
+                Some(&kept) => {
+                    duplicates_dropped += 1;
+                    if channels[kept].group != channel.group {
+                        collisions.push(GroupCollision {
+                            name: channel.name,
+                            kept_group: channels[kept].group.clone(),
+                            dropped_group: channel.group,
+                            source_index,
+                        });
+                    }
+                }
+                None => {
+                    seen.insert(id, channels.len());
+                    channels.push(channel);
+                }
+            }
+        }
+    }
+
+    log::info!(
+        "Merged {} playlists: {} channels, {} duplicates dropped, {} group collisions",
+        sources.len(),
+        channels.len(),
+        duplicates_dropped,
+        collisions.len()
+    );
+
+    MergedPlaylist {
+        channels,
+        duplicates_dropped,
+        collisions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYLIST: &str = r#"#EXTM3U tvg-shift="+1"
+#EXTGRP:News
+#EXTINF:-1 tvg-id="bbc.uk" tvg-name="BBC News" tvg-logo="http://logos.example/bbc.png",BBC News HD
+#EXTVLCOPT:http-user-agent=Mozilla/5.0 (X11; Linux)
+#EXTVLCOPT:http-referrer=http://portal.example/
+http://streams.example/live/bbc.m3u8
+#EXTINF:-1 tvg-id="movies.de" group-title="Movies" tvg-shift="-0.5",Movies 24
+#KODIPROP:inputstream.adaptive.license_type=clearkey
+#KODIPROP:inputstream.adaptive.license_key=0011:2233
+http://streams.example/live/movies.mpd
+#EXTINF:3600,Documentary
+http://streams.example/vod/doc.mp4
+"#;
+
+    #[test]
+    fn serialize_then_parse_keeps_recognized_fields() {
+        let first = parse_m3u_content(PLAYLIST);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].group.as_deref(), Some("News"));
+        assert_eq!(first[0].tvg_shift, Some(1.0));
+        assert_eq!(first[0].http_headers.len(), 2);
+        assert_eq!(first[1].tvg_shift, Some(-0.5));
+        assert_eq!(first[1].props.len(), 2);
+        assert_eq!(first[2].duration, Some(3600));
+
+        let exported = channels_to_m3u_content(&first);
+        let second = parse_m3u_content(&exported);
+        assert_eq!(second, first);
+        assert_eq!(channels_to_m3u_content(&second), exported);
+    }
+}
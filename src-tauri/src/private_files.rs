@@ -0,0 +1,62 @@
+// ========================================
+// Private Temp Files
+// ========================================
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Directory under the app's temp root holding staged and generated tunnel configs
+pub const TUNNEL_CONFIG_DIR: &str = "tunnels";
+
+/// `name` under the current user's temp root, created if needed and restricted to the
+/// current user. On Windows the temp folder is already per-user, so no extra ACLs are set.
+pub fn private_dir(name: &str) -> Result<PathBuf, String> {
+    let dir = crate::temp_dirs::ensure_root()?.join(name);
+    create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+/// Create `dir` if needed and restrict it to the current user (Unix only)
+pub fn create_private_dir(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to restrict {}: {}", dir.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Write `content` to a fresh file only the current user can read. Anything already at
+/// `path` is removed first, so a planted file or symlink is never written through.
+pub fn write_private(path: &Path, content: &[u8]) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("Failed to replace {}: {}", path.display(), e));
+        }
+        _ => {}
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    file.write_all(content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Remove a file written by `write_private`, ignoring one that's already gone
+pub fn remove_private(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// ========================================
+// Stream Recording
+// ========================================
+
+use crate::console::{self, ConsoleLevel};
+use crate::errors::CommandError;
+use crate::sessions::ActiveSessions;
+use crate::{ffmpeg, stream_proxy};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+
+/// How long `stop_recording` waits for FFmpeg to finalize before killing it
+const STOP_TIMEOUT_SECS: u64 = 10;
+
+/// Sequence number appended to ids
+static NEXT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+struct RecordingSegmentEvent {
+    recording_id: String,
+    path: String,
+    index: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    pub recording_id: String,
+    pub output_path: String,
+    pub segment_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingResult {
+    pub recording_id: String,
+    /// Finished files in order; a single entry unless segmented
+    pub files: Vec<String>,
+    pub duration_secs: u64,
+}
+
+pub struct RecordingSession {
+    output_path: String,
+    started: std::time::Instant,
+    child: Child,
+    /// Completed segment files, filled by the stderr reader
+    segments: Arc<Mutex<Vec<String>>>,
+    stderr_reader: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+/// `show.ts` -> `show_%03d.ts`, the numbered pattern for the segment muxer
+fn segment_pattern(output_path: &str) -> String {
+    let path = Path::new(output_path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("ts");
+    path.with_file_name(format!("{}_%03d.{}", stem, ext))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// File name from FFmpeg's `Opening 'out_001.ts' for writing` line
+fn opened_file(line: &str) -> Option<&str> {
+    let rest = &line[line.find("Opening '")? + "Opening '".len()..];
+    let end = rest.find("' for writing")?;
+    Some(&rest[..end])
+}
+
+/// Record `source_url` to `output_path` without re-encoding. With `segment_secs`,
+/// FFmpeg rolls over into numbered files cut on keyframes, so each one plays on its own,
+/// and a `recording://segment` event is emitted as each file is completed.
+#[tauri::command]
+pub async fn start_recording(
+    app: AppHandle,
+    sessions: State<'_, ActiveSessions>,
+    source_url: String,
+    output_path: String,
+    headers: Option<Vec<(String, String)>>,
+    segment_secs: Option<u64>,
+) -> Result<RecordingInfo, CommandError> {
+    log::info!("Recording {} to {} (segment_secs: {:?})", source_url, output_path, segment_secs);
+
+    if segment_secs == Some(0) {
+        return Err("segment_secs must be greater than 0".into());
+    }
+    let ffmpeg_path = ffmpeg::require_ffmpeg()?;
+    let headers = headers.unwrap_or_default();
+
+    // Info level so the segment muxer reports each file it opens
+    let mut args: Vec<String> = vec!["-hide_banner".into(), "-loglevel".into(), "info".into(), "-nostats".into()];
+    args.extend(stream_proxy::header_args(&source_url, &headers));
+    if let Some(transport) = stream_proxy::rtsp_transport(&source_url, "", None)? {
+        args.extend(["-rtsp_transport".into(), transport.into()]);
+    }
+    args.extend(["-i".into(), source_url.clone(), "-c".into(), "copy".into()]);
+    match segment_secs {
+        Some(secs) => args.extend([
+            "-f".into(), "segment".into(),
+            "-segment_time".into(), secs.to_string(),
+            "-reset_timestamps".into(), "1".into(),
+            segment_pattern(&output_path),
+        ]),
+        None => args.extend(["-y".into(), output_path.clone()]),
+    }
+
+    log::info!("Executing FFmpeg: {} {}", ffmpeg_path, args.join(" "));
+
+    let mut command = Command::new(&ffmpeg_path);
+    command
+        .args(&args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start FFmpeg: {}", e))?;
+
+    // The sequence number keeps ids of jobs started in the same millisecond apart
+    let recording_id = format!(
+        "rec-{}-{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default(),
+        NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    );
+
+    // A segment is complete once FFmpeg opens the next one, or exits
+    let segments = Arc::new(Mutex::new(Vec::new()));
+    let stderr_reader = child.stderr.take().map(|stderr| {
+        let app = app.clone();
+        let recording_id = recording_id.clone();
+        let segments = segments.clone();
+        let segmented = segment_secs.is_some();
+        let mut current = (!segmented).then(|| output_path.clone());
+        tauri::async_runtime::spawn(async move {
+            let finish = |path: String| {
+                let mut done = segments.lock().unwrap_or_else(|e| e.into_inner());
+                done.push(path.clone());
+                if segmented {
+                    let _ = app.emit("recording://segment", RecordingSegmentEvent {
+                        recording_id: recording_id.clone(),
+                        path,
+                        index: done.len() - 1,
+                    });
+                }
+            };
+
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if segmented && line.contains("[segment @") {
+                    if let Some(opened) = opened_file(&line) {
+                        if let Some(previous) = current.replace(opened.to_string()) {
+                            finish(previous);
+                        }
+                        continue;
+                    }
+                }
+                if line.to_lowercase().contains("error") {
+                    console::record(ConsoleLevel::Error, "ffmpeg", format!("[recording] {}", line));
+                }
+            }
+            if let Some(last) = current.take() {
+                finish(last);
+            }
+        })
+    });
+
+    sessions.recordings.lock().await.insert(recording_id.clone(), RecordingSession {
+        output_path: output_path.clone(),
+        started: std::time::Instant::now(),
+        child,
+        segments,
+        stderr_reader,
+    });
+
+    Ok(RecordingInfo {
+        recording_id,
+        output_path,
+        segment_secs,
+    })
+}
+
+/// Stop a recording, letting FFmpeg finalize the current file before it exits
+#[tauri::command]
+pub async fn stop_recording(
+    sessions: State<'_, ActiveSessions>,
+    recording_id: String,
+) -> Result<RecordingResult, String> {
+    let mut session = sessions.recordings.lock().await
+        .remove(&recording_id)
+        .ok_or_else(|| format!("No recording with id {}", recording_id))?;
+
+    log::info!("Stopping recording {} ({})", recording_id, session.output_path);
+
+    // 'q' on stdin makes FFmpeg write trailers and close the file properly
+    if let Some(mut stdin) = session.child.stdin.take() {
+        let _ = stdin.write_all(b"q").await;
+        let _ = stdin.flush().await;
+    }
+    let exited = tokio::time::timeout(
+        std::time::Duration::from_secs(STOP_TIMEOUT_SECS),
+        session.child.wait(),
+    ).await;
+    if exited.is_err() {
+        log::warn!("FFmpeg didn't finish recording {} in time, killing it", recording_id);
+        let _ = session.child.kill().await;
+    }
+    if let Some(reader) = session.stderr_reader.take() {
+        let _ = reader.await;
+    }
+
+    let files = session.segments.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    Ok(RecordingResult {
+        recording_id,
+        files,
+        duration_secs: session.started.elapsed().as_secs(),
+    })
+}
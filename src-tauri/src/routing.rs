@@ -0,0 +1,98 @@
+// ========================================
+// VPN Routing Conflicts
+// ========================================
+
+use crate::sessions::ActiveSessions;
+use crate::VpnType;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RouteMatch {
+    pub address: String,
+    /// Most specific routed range containing the address
+    pub cidr: String,
+    pub tunnel_name: String,
+    pub vpn_type: VpnType,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoutingConflictReport {
+    pub host: String,
+    pub addresses: Vec<String>,
+    pub conflicts: Vec<RouteMatch>,
+    pub warning: Option<String>,
+}
+
+/// Parse an `AllowedIPs` entry; a bare address is a single-host route
+fn parse_route(entry: &str) -> Option<IpNet> {
+    let entry = entry.trim();
+    entry.parse::<IpNet>().ok()
+        .or_else(|| entry.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+async fn resolve_host(host: &str, port: u16) -> Result<Vec<IpAddr>, String> {
+    // IPv6 literals come bracketed from the URL
+    let bare = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = bare.parse::<IpAddr>() {
+        return Ok(vec![ip]);
+    }
+    let mut addresses: Vec<IpAddr> = tokio::net::lookup_host((bare, port)).await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .map(|addr| addr.ip())
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    Ok(addresses)
+}
+
+/// Check whether the stream host resolves into a range routed by a connected tunnel
+#[tauri::command]
+pub async fn check_routing_conflict(
+    sessions: State<'_, ActiveSessions>,
+    source_url: String,
+) -> Result<RoutingConflictReport, String> {
+    let url = url::Url::parse(&source_url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let host = url.host_str().ok_or("URL has no host")?.to_string();
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let addresses = resolve_host(&host, port).await?;
+    let tunnels = sessions.tunnels.lock().await.clone();
+
+    let mut conflicts = Vec::new();
+    for address in &addresses {
+        for (tunnel_name, tunnel) in &tunnels {
+            let best = tunnel.allowed_ips.iter()
+                .filter_map(|entry| parse_route(entry))
+                .filter(|net| net.contains(address))
+                .max_by_key(|net| net.prefix_len());
+            if let Some(net) = best {
+                conflicts.push(RouteMatch {
+                    address: address.to_string(),
+                    cidr: net.to_string(),
+                    tunnel_name: tunnel_name.clone(),
+                    vpn_type: tunnel.vpn_type.clone(),
+                });
+            }
+        }
+    }
+
+    let warning = conflicts.first().map(|first| {
+        format!(
+            "{} resolves to {}, which is routed through tunnel '{}' ({}). The stream will go through the VPN.",
+            host, first.address, first.tunnel_name, first.cidr
+        )
+    });
+    if let Some(ref warning) = warning {
+        log::info!("{}", warning);
+    }
+
+    Ok(RoutingConflictReport {
+        host,
+        addresses: addresses.iter().map(|a| a.to_string()).collect(),
+        conflicts,
+        warning,
+    })
+}
@@ -0,0 +1,74 @@
+// ========================================
+// Secret Detection
+// ========================================
+//
+// Shared by the diagnostics report and the state backup, so both treat the same
+// fields and URL parts as credentials.
+
+/// Object keys whose values are secrets, compared lowercase without `_` / `-`
+const SENSITIVE_KEYS: &[&str] = &[
+    "password", "passwd", "privatekey", "presharedkey", "secret", "token", "apikey",
+    "authorization", "cookie", "licensekey",
+];
+
+/// URL query parameters that carry credentials (Xtream-style playlist URLs)
+const SENSITIVE_PARAMS: &[&str] = &["password", "pass", "token", "key"];
+
+/// Xtream stream paths with the credentials in the path, as the number of segments:
+/// `/live/<user>/<pass>/<id>.ts` and `/timeshift/<user>/<pass>/<minutes>/<start>/<id>.ts`
+const XTREAM_PATHS: &[(&str, usize)] = &[("live", 4), ("movie", 4), ("series", 4), ("timeshift", 6)];
+
+pub fn is_sensitive_key(key: &str) -> bool {
+    let key: String = key.chars().filter(|c| *c != '_' && *c != '-').collect::<String>().to_lowercase();
+    SENSITIVE_KEYS.contains(&key.as_str())
+}
+
+fn is_sensitive_param(name: &str) -> bool {
+    SENSITIVE_PARAMS.contains(&name.to_lowercase().as_str())
+}
+
+/// Index of the password segment when `url` is an Xtream stream path that has one
+fn xtream_password_segment(url: &url::Url) -> Option<usize> {
+    let segments: Vec<&str> = url.path_segments()?.collect();
+    let (_, len) = XTREAM_PATHS.iter().find(|(kind, _)| segments.first() == Some(kind))?;
+    (segments.len() == *len && !segments[2].is_empty()).then_some(2)
+}
+
+/// The URL with its password, credential query parameters and Xtream path password
+/// replaced by `mask` (dropped when `mask` is empty), or `None` if it had none
+pub fn redact_url(value: &str, mask: &str) -> Option<String> {
+    if !value.starts_with("http://") && !value.starts_with("https://") {
+        return None;
+    }
+    let mut url = url::Url::parse(value).ok()?;
+    let has_password = url.password().is_some();
+    let has_params = url.query_pairs().any(|(k, _)| is_sensitive_param(&k));
+    let path_password = xtream_password_segment(&url);
+    if !has_password && !has_params && path_password.is_none() {
+        return None;
+    }
+
+    if has_password {
+        let _ = url.set_password((!mask.is_empty()).then_some(mask));
+    }
+    if has_params {
+        let kept: Vec<(String, String)> = url.query_pairs()
+            .filter_map(|(k, v)| match is_sensitive_param(&k) {
+                true if mask.is_empty() => None,
+                true => Some((k.into_owned(), mask.to_string())),
+                false => Some((k.into_owned(), v.into_owned())),
+            })
+            .collect();
+        if kept.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(kept);
+        }
+    }
+    if let Some(index) = path_password {
+        let mut segments: Vec<String> = url.path_segments()?.map(str::to_string).collect();
+        segments[index] = mask.to_string();
+        url.set_path(&segments.join("/"));
+    }
+    Some(url.to_string())
+}
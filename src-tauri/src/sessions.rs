@@ -0,0 +1,49 @@
+// ========================================
+// Active Sessions
+// ========================================
+
+use crate::ffmpeg_jobs::FfmpegJob;
+use crate::recording::RecordingSession;
+use crate::stream_proxy::ProxySession;
+use crate::VpnType;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// A tunnel brought up by `connect_vpn`, with the ranges it routes
+#[derive(Debug, Clone)]
+pub struct ConnectedTunnel {
+    pub vpn_type: VpnType,
+    pub allowed_ips: Vec<String>,
+    /// Resolvers pushed by the tunnel config (`DNS =` / `dhcp-option DNS`)
+    pub dns_servers: Vec<std::net::IpAddr>,
+    /// Removed when the app exits instead of persisting as a service
+    pub ephemeral: bool,
+    /// Config written for `connect_vpn_from_content`, deleted on disconnect
+    pub temp_config: Option<std::path::PathBuf>,
+    /// Config the tunnel was connected with, reused by `reconnect_vpn`
+    pub config_path: String,
+    /// `Address =` of the config, to pick the tunnel's own address among the interface's
+    pub address: Option<String>,
+    /// When `connect_vpn` brought the tunnel up, for its uptime
+    pub connected_at: std::time::Instant,
+}
+
+/// Long-running processes and servers started by the app, managed as Tauri state
+#[derive(Default)]
+pub struct ActiveSessions {
+    /// Stream proxies keyed by source URL
+    pub proxies: Mutex<HashMap<String, ProxySession>>,
+    /// Source URLs whose proxy is being started; std Mutex since it's released on drop
+    pub starting_proxies: std::sync::Mutex<HashSet<String>>,
+    /// In-flight `connect_vpn` calls keyed by request id
+    pub pending_connects: Mutex<HashMap<String, CancellationToken>>,
+    /// Tunnels connected by this app keyed by tunnel name
+    pub tunnels: Mutex<HashMap<String, ConnectedTunnel>>,
+    /// Recordings in progress keyed by recording id
+    pub recordings: Mutex<HashMap<String, RecordingSession>>,
+    /// Custom `run_ffmpeg` pipelines keyed by session id
+    pub ffmpeg_jobs: Mutex<HashMap<String, FfmpegJob>>,
+    /// Scratch directories from `temp_dirs::allocate`, removed when their session stops
+    pub temp_dirs: Mutex<HashSet<std::path::PathBuf>>,
+}
@@ -0,0 +1,110 @@
+// ========================================
+// Persisted App Settings
+// ========================================
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Headers added to every `http_fetch` whose host matches `host_pattern`: an exact
+/// host, `*.example.com` for the domain and its subdomains, or `*` for all hosts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderRule {
+    pub host_pattern: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl HeaderRule {
+    pub fn matches(&self, host: &str) -> bool {
+        let pattern = self.host_pattern.trim().to_lowercase();
+        let host = host.to_lowercase();
+        if pattern == "*" {
+            return true;
+        }
+        match pattern.strip_prefix("*.") {
+            Some(domain) => host == domain || host.ends_with(&format!(".{}", domain)),
+            None => host == pattern,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Let the shared HTTP client use HTTP(S)_PROXY and the OS proxy configuration
+    pub respect_system_proxy: bool,
+    /// Origin the embedded HLS server admits via CORS: `None` for the app's own webview
+    /// only, `"*"` (an explicit opt-in) for any page. Renamed from `proxy_cors_origin`,
+    /// whose `"*"` default isn't carried over.
+    pub proxy_allowed_origin: Option<String>,
+    /// Global `http_fetch` headers, applied in order so later rules override earlier ones
+    pub header_rules: Vec<HeaderRule>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            respect_system_proxy: true,
+            proxy_allowed_origin: None,
+            header_rules: Vec::new(),
+        }
+    }
+}
+
+/// Settings loaded from the app config directory, managed as Tauri state
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    settings: Mutex<AppSettings>,
+}
+
+impl SettingsStore {
+    /// Load settings, falling back to defaults if the file is missing or unreadable
+    pub fn load(app: &AppHandle) -> Self {
+        let path = app.path().app_config_dir().ok().map(|dir| dir.join(SETTINGS_FILE));
+        let settings = path.as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(settings) => Some(settings),
+                Err(e) => {
+                    log::warn!("Ignoring invalid settings file: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            path,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    pub fn get(&self) -> AppSettings {
+        self.settings.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Apply `change` to a copy, write it to disk, then make it current. On a write
+    /// error the in-memory settings are left unchanged.
+    pub fn update(&self, change: impl FnOnce(&mut AppSettings)) -> Result<AppSettings, String> {
+        // The lock stays held so concurrent updates apply one after another
+        let mut settings = self.settings.lock().unwrap_or_else(|e| e.into_inner());
+        let mut updated = settings.clone();
+        change(&mut updated);
+
+        let path = self.path.as_ref().ok_or("App config directory is not available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(&updated)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+        // Only a saved change becomes visible, so memory never runs ahead of the file
+        *settings = updated.clone();
+        Ok(updated)
+    }
+}
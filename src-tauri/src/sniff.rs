@@ -0,0 +1,150 @@
+// ========================================
+// Media Type Sniffing
+// ========================================
+
+use crate::http_client::SharedHttpClient;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Enough for several TS packets and the head of a playlist or MPD
+const SNIFF_BYTES: usize = 16 * 1024;
+
+const SNIFF_TIMEOUT_SECS: u64 = 10;
+
+const TS_PACKET: usize = 188;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Hls,
+    Dash,
+    Mpegts,
+    Mp4,
+    Mkv,
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MediaProbe {
+    pub kind: MediaKind,
+    /// URL after redirects
+    pub final_url: String,
+    pub content_type: Option<String>,
+    pub status: u16,
+    pub bytes_read: usize,
+    /// True when the guess came from the bytes, false when only the Content-Type matched
+    pub from_content: bool,
+}
+
+/// Sync bytes at the same offset in three consecutive TS packets (two if that's all we have)
+fn looks_like_mpegts(bytes: &[u8]) -> bool {
+    let packets = match bytes.len() / TS_PACKET {
+        0 | 1 => return false,
+        2 => 2,
+        _ => 3,
+    };
+    (0..TS_PACKET).any(|offset| {
+        (0..packets).all(|i| bytes.get(offset + i * TS_PACKET) == Some(&0x47))
+    })
+}
+
+fn kind_from_bytes(bytes: &[u8]) -> Option<MediaKind> {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with("#EXTM3U") {
+        return Some(MediaKind::Hls);
+    }
+    if text.starts_with('<') && text.contains("<MPD") {
+        return Some(MediaKind::Dash);
+    }
+    if matches!(bytes.get(4..8), Some(b"ftyp" | b"styp" | b"moov" | b"moof")) {
+        return Some(MediaKind::Mp4);
+    }
+    if bytes.starts_with(&[0x1a, 0x45, 0xdf, 0xa3]) {
+        return Some(MediaKind::Mkv);
+    }
+    if looks_like_mpegts(bytes) {
+        return Some(MediaKind::Mpegts);
+    }
+    None
+}
+
+fn kind_from_content_type(content_type: &str) -> Option<MediaKind> {
+    let content_type = content_type.to_lowercase();
+    if content_type.contains("mpegurl") {
+        Some(MediaKind::Hls)
+    } else if content_type.contains("dash+xml") {
+        Some(MediaKind::Dash)
+    } else if content_type.contains("mp2t") {
+        Some(MediaKind::Mpegts)
+    } else if content_type.contains("mp4") {
+        Some(MediaKind::Mp4)
+    } else if content_type.contains("matroska") || content_type.contains("webm") {
+        Some(MediaKind::Mkv)
+    } else {
+        None
+    }
+}
+
+/// Guess what `url` really serves from the first bytes of the response, following
+/// redirects. Reads at most 16 KiB with a ranged GET, so servers that ignore the range
+/// are cut off there too.
+#[tauri::command]
+pub async fn sniff_media(
+    shared_client: State<'_, SharedHttpClient>,
+    url: String,
+) -> Result<MediaProbe, String> {
+    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!("Unsupported URL scheme: {}", parsed.scheme()));
+    }
+
+    let probe = async {
+        let mut response = shared_client.get()
+            .get(parsed)
+            .header(reqwest::header::RANGE, format!("bytes=0-{}", SNIFF_BYTES - 1))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let status = response.status();
+        let final_url = response.url().to_string();
+        let content_type = response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        if !status.is_success() {
+            return Err(format!("Server returned status {}", status));
+        }
+
+        let mut bytes = Vec::with_capacity(SNIFF_BYTES);
+        while bytes.len() < SNIFF_BYTES {
+            match response.chunk().await {
+                Ok(Some(chunk)) => bytes.extend_from_slice(&chunk),
+                Ok(None) => break,
+                Err(e) if bytes.is_empty() => return Err(format!("Failed to read body: {}", e)),
+                Err(_) => break,
+            }
+        }
+        bytes.truncate(SNIFF_BYTES);
+
+        let from_bytes = kind_from_bytes(&bytes);
+        let kind = from_bytes
+            .or_else(|| content_type.as_deref().and_then(kind_from_content_type))
+            .unwrap_or(MediaKind::Unknown);
+        Ok(MediaProbe {
+            kind,
+            final_url,
+            content_type,
+            status: status.as_u16(),
+            bytes_read: bytes.len(),
+            from_content: from_bytes.is_some(),
+        })
+    };
+
+    let probe = tokio::time::timeout(std::time::Duration::from_secs(SNIFF_TIMEOUT_SECS), probe)
+        .await
+        .map_err(|_| format!("Timed out probing {}", url))??;
+    log::info!("Sniffed {} as {:?} ({} bytes, final URL {})", url, probe.kind, probe.bytes_read, probe.final_url);
+    Ok(probe)
+}
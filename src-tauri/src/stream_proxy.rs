@@ -0,0 +1,1365 @@
+// ========================================
+// Stream Proxy (RTSP/RTMP -> HLS, HLS relay)
+// ========================================
+
+use crate::console::{self, ConsoleLevel};
+use crate::errors::CommandError;
+use crate::ffmpeg;
+use crate::local_tls::LocalCert;
+use crate::sessions::ActiveSessions;
+use crate::settings::SettingsStore;
+use crate::temp_dirs;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::process::{Child, Command};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+
+/// HLS segment length in seconds; transcoded keyframes are forced on this boundary
+const HLS_SEGMENT_SECS: u32 = 2;
+
+/// How long to wait for FFmpeg to write the first playlist
+const STARTUP_TIMEOUT_SECS: u64 = 20;
+
+/// FFmpeg stderr lines kept per proxy
+const PROXY_LOG_LINES: usize = 500;
+
+/// stderr lines included in the error when a proxy fails to start
+const STARTUP_ERROR_LINES: usize = 5;
+
+/// RTSP socket timeout passed to FFmpeg, in microseconds
+const RTSP_TIMEOUT_US: u64 = 10_000_000;
+
+/// Consecutive accept failures after which the server gives up and the watchdog rebinds
+const MAX_ACCEPT_ERRORS: u32 = 20;
+
+/// Upper bound on segment bytes a relay keeps prefetched
+const SEGMENT_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Origins of the app's webview (macOS/Linux, Windows with http and https schemes)
+const APP_ORIGINS: &[&str] = &["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost"];
+
+/// `devUrl` the webview loads in debug builds
+const DEV_ORIGIN: &str = "http://localhost:5173";
+
+/// Largest upstream playlist or segment the relay reads into memory
+const MAX_RELAY_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Upstream URLs from rewritten manifests the relay keeps serving, oldest dropped first
+const MAX_RELAY_URLS: usize = 4096;
+
+/// Time a client gets to send its request line and headers
+const REQUEST_HEAD_TIMEOUT_SECS: u64 = 10;
+
+/// Longest request or header line, and most header lines, the embedded server reads
+const MAX_HEAD_LINE_BYTES: u64 = 8 * 1024;
+const MAX_HEADER_LINES: usize = 100;
+
+/// Optional transcoding settings; without a profile the source is remuxed as-is
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TranscodeProfile {
+    /// Video encoder, e.g. "libx264", "h264_nvenc", "h264_qsv" or "h264_videotoolbox"
+    pub video_encoder: Option<String>,
+    /// Target video bitrate, e.g. "2500k"
+    pub video_bitrate: Option<String>,
+    /// Downscale to at most this height, keeping the aspect ratio
+    pub max_height: Option<u32>,
+}
+
+/// Ring buffer of recent FFmpeg stderr lines, oldest first
+#[derive(Clone, Default)]
+pub struct LogBuffer(Arc<std::sync::Mutex<VecDeque<String>>>);
+
+impl LogBuffer {
+    fn push(&self, line: String) {
+        let mut lines = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == PROXY_LOG_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// The last `count` lines
+    pub fn tail(&self, count: usize) -> Vec<String> {
+        let lines = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().skip(lines.len().saturating_sub(count)).cloned().collect()
+    }
+}
+
+/// Request counters of one proxy's embedded server
+#[derive(Default)]
+pub struct ProxyStats {
+    requests: AtomicU64,
+    bytes_served: AtomicU64,
+    /// Relay segments answered from the prefetch cache, or fetched upstream on demand
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+pub struct ProxySession {
+    pub output_port: u16,
+    pub hls_url: String,
+    /// Segment directory, only for FFmpeg-backed proxies
+    pub output_dir: Option<PathBuf>,
+    pub logs: LogBuffer,
+    child: Option<Child>,
+    /// Watchdog that runs the embedded server and restarts it if it dies
+    server: tauri::async_runtime::JoinHandle<()>,
+    /// Stops the server, its watchdog and the relay's segment prefetcher
+    stop: CancellationToken,
+    stats: Arc<ProxyStats>,
+}
+
+impl ProxySession {
+    async fn shutdown(mut self, sessions: &ActiveSessions) {
+        if let Some(child) = self.child.as_mut() {
+            if let Err(e) = child.kill().await {
+                log::warn!("Failed to stop FFmpeg: {}", e);
+            }
+        }
+        // The watchdog aborts the server itself, so wait for it to release the port
+        self.stop.cancel();
+        let _ = self.server.await;
+        if let Some(dir) = &self.output_dir {
+            temp_dirs::release(sessions, dir).await;
+        }
+    }
+}
+
+/// Embedded server state shared by all connections of one proxy
+struct ProxyServer {
+    route: ProxyRoute,
+    /// `AppSettings::proxy_allowed_origin` when the proxy started
+    allowed_origin: Option<String>,
+    stats: Arc<ProxyStats>,
+    /// Serve HTTPS with the local certificate instead of plain HTTP
+    tls: Option<TlsAcceptor>,
+}
+
+/// Playlist URL a proxy on `port` is reachable at
+fn proxy_url(port: u16, https: bool) -> String {
+    let scheme = if https { "https" } else { "http" };
+    format!("{}://localhost:{}/stream/index.m3u8", scheme, port)
+}
+
+/// What the embedded server serves for a proxy
+enum ProxyRoute {
+    /// Playlist and segments FFmpeg writes into the session directory
+    Files(PathBuf),
+    /// Upstream HLS fetched on demand with the channel's headers
+    Relay(HlsRelay),
+}
+
+struct HlsRelay {
+    client: reqwest::Client,
+    manifest_url: reqwest::Url,
+    headers: reqwest::header::HeaderMap,
+    /// Segments to fetch ahead of the player, 0 to disable
+    prefetch_count: usize,
+    cache: Arc<std::sync::Mutex<SegmentCache>>,
+    prefetch_stop: CancellationToken,
+}
+
+/// Prefetched segments by upstream URL, evicted oldest first to stay under `SEGMENT_CACHE_BYTES`
+#[derive(Default)]
+struct SegmentCache {
+    entries: HashMap<String, (String, Vec<u8>)>,
+    order: VecDeque<String>,
+    bytes: usize,
+    /// Segment URLs of each media playlist seen, and whether it is VOD
+    playlists: HashMap<String, (Vec<String>, bool)>,
+    /// Last segment the player fetched
+    last_served: Option<String>,
+    in_flight: HashSet<String>,
+    /// URLs the relay wrote into a manifest; `/stream/relay` serves nothing else
+    relay_urls: HashSet<String>,
+    relay_order: VecDeque<String>,
+}
+
+impl SegmentCache {
+    fn insert(&mut self, url: String, content_type: String, body: Vec<u8>) {
+        if body.len() > SEGMENT_CACHE_BYTES || self.entries.contains_key(&url) {
+            return;
+        }
+        while self.bytes + body.len() > SEGMENT_CACHE_BYTES {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some((_, evicted)) = self.entries.remove(&oldest) {
+                self.bytes -= evicted.len();
+            }
+        }
+        self.bytes += body.len();
+        self.order.push_back(url.clone());
+        self.entries.insert(url, (content_type, body));
+    }
+
+    /// Up to `count` segments following `current`, which is a segment or a media playlist URL
+    fn upcoming(&self, current: &str, count: usize) -> Vec<String> {
+        if let Some((segments, is_vod)) = self.playlists.get(current) {
+            // On a refresh, continue after the player's position; at first, from where playback starts
+            let start = self.last_served.as_ref()
+                .and_then(|last| segments.iter().position(|s| s == last))
+                .map(|pos| pos + 1)
+                .unwrap_or(if *is_vod { 0 } else { segments.len().saturating_sub(count) });
+            return segments.iter().skip(start).take(count).cloned().collect();
+        }
+        self.playlists.values()
+            .find_map(|(segments, _)| {
+                let pos = segments.iter().position(|s| s == current)?;
+                Some(segments[pos + 1..].iter().take(count).cloned().collect())
+            })
+            .unwrap_or_default()
+    }
+
+    fn allow(&mut self, urls: Vec<String>) {
+        for url in urls {
+            if self.relay_urls.insert(url.clone()) {
+                self.relay_order.push_back(url);
+            }
+        }
+        while self.relay_order.len() > MAX_RELAY_URLS {
+            if let Some(oldest) = self.relay_order.pop_front() {
+                self.relay_urls.remove(&oldest);
+            }
+        }
+    }
+
+    fn is_segment(&self, url: &str) -> bool {
+        self.playlists.values().any(|(segments, _)| segments.iter().any(|s| s == url))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EncoderFallbackEvent {
+    source_url: String,
+    requested: String,
+    used: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PortChangedEvent {
+    source_url: String,
+    old_port: u16,
+    new_port: u16,
+    hls_url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProxyLogEvent {
+    source_url: String,
+    line: String,
+}
+
+/// Build the FFmpeg video/audio arguments for an optional transcode profile
+fn codec_args(profile: Option<&TranscodeProfile>, encoder: &str) -> Vec<String> {
+    let Some(profile) = profile else {
+        return vec!["-c".into(), "copy".into()];
+    };
+
+    let mut args = vec!["-c:v".to_string(), encoder.to_string()];
+    if encoder == ffmpeg::SOFTWARE_ENCODER {
+        args.extend(["-preset".into(), "veryfast".into()]);
+    }
+    if let Some(bitrate) = &profile.video_bitrate {
+        args.extend(["-b:v".into(), bitrate.clone()]);
+    }
+    if let Some(height) = profile.max_height {
+        args.extend(["-vf".into(), format!("scale=-2:'min({},ih)'", height)]);
+    }
+    // Keyframe on every segment boundary so each segment starts cleanly
+    args.extend([
+        "-force_key_frames".into(),
+        format!("expr:gte(t,n_forced*{})", HLS_SEGMENT_SECS),
+        "-c:a".into(),
+        "aac".into(),
+    ]);
+    args
+}
+
+/// Channel headers as a reqwest header map, skipping entries that aren't valid HTTP
+fn header_map(headers: &[(String, String)]) -> reqwest::header::HeaderMap {
+    let mut map = reqwest::header::HeaderMap::new();
+    for (key, value) in headers {
+        match (
+            reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                map.insert(name, value);
+            }
+            _ => log::warn!("Ignoring invalid header: {}", key),
+        }
+    }
+    map
+}
+
+/// FFmpeg input options carrying the channel headers
+pub fn header_args(source_url: &str, headers: &[(String, String)]) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut extra = String::new();
+    for (key, value) in headers {
+        if key.eq_ignore_ascii_case("user-agent") {
+            args.extend(["-user_agent".to_string(), value.clone()]);
+        } else {
+            extra.push_str(&format!("{}: {}\r\n", key, value));
+        }
+    }
+    // -headers only exists for HTTP inputs; the RTSP demuxer rejects it
+    let is_http = source_url.starts_with("http://") || source_url.starts_with("https://");
+    if is_http && !extra.is_empty() {
+        args.extend(["-headers".to_string(), extra]);
+    }
+    args
+}
+
+/// RTSP lower transport for this source: `protocol` "rtsp+tcp"/"rtsp+udp" or
+/// `transport` select it, defaulting to TCP which gets through NAT more reliably.
+/// Returns `None` for non-RTSP sources.
+pub fn rtsp_transport(source_url: &str, protocol: &str, transport: Option<&str>) -> Result<Option<&'static str>, String> {
+    let protocol = protocol.to_lowercase();
+    let is_rtsp = protocol.starts_with("rtsp") || source_url.to_lowercase().starts_with("rtsp");
+    if !is_rtsp {
+        return Ok(None);
+    }
+
+    let requested = transport
+        .map(|t| t.to_lowercase())
+        .or_else(|| protocol.split_once('+').map(|(_, t)| t.to_string()));
+    match requested.as_deref() {
+        None | Some("tcp") => Ok(Some("tcp")),
+        Some("udp") => Ok(Some("udp")),
+        Some(other) => Err(format!("Unsupported RTSP transport '{}', expected tcp or udp", other)),
+    }
+}
+
+/// Marks a `source_url` whose proxy is being started in `ActiveSessions::starting_proxies`,
+/// until the start succeeds or fails
+struct StartReservation<'a> {
+    sessions: &'a ActiveSessions,
+    source_url: String,
+}
+
+impl<'a> StartReservation<'a> {
+    fn acquire(sessions: &'a ActiveSessions, source_url: &str) -> Result<Self, String> {
+        let mut starting = sessions.starting_proxies.lock().unwrap_or_else(|e| e.into_inner());
+        if !starting.insert(source_url.to_string()) {
+            return Err(format!("A proxy for {} is already starting", source_url));
+        }
+        Ok(Self { sessions, source_url: source_url.to_string() })
+    }
+}
+
+impl Drop for StartReservation<'_> {
+    fn drop(&mut self) {
+        self.sessions.starting_proxies.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.source_url);
+    }
+}
+
+/// Track a started proxy, stopping any session it replaces so nothing leaks
+async fn register_proxy(sessions: &ActiveSessions, source_url: String, session: ProxySession) {
+    let replaced = sessions.proxies.lock().await.insert(source_url.clone(), session);
+    if let Some(replaced) = replaced {
+        log::warn!("Stopping the proxy replaced for {}", source_url);
+        replaced.shutdown(sessions).await;
+    }
+}
+
+/// Start a local HLS proxy for `source_url` on `output_port`.
+/// With protocol "hls" the upstream playlist is relayed as-is; anything else
+/// (RTSP, RTMP, raw TS, ...) is converted to HLS by FFmpeg.
+/// `headers` (e.g. from `#EXTVLCOPT`) are sent on every upstream request.
+/// RTSP sources use TCP unless `protocol` is "rtsp+udp" or `transport` is "udp".
+/// For relayed HLS, `prefetch_segments` fetches that many segments ahead of the player.
+/// With `https` the proxy is served over TLS with the local certificate and returns an
+/// `https://` URL; browsers only accept it once `trust_local_cert` was run.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_stream_proxy(
+    app: AppHandle,
+    sessions: State<'_, ActiveSessions>,
+    source_url: String,
+    output_port: u16,
+    protocol: String,
+    transcode: Option<TranscodeProfile>,
+    headers: Option<Vec<(String, String)>>,
+    transport: Option<String>,
+    prefetch_segments: Option<u8>,
+    https: Option<bool>,
+) -> Result<serde_json::Value, CommandError> {
+    log::info!(
+        "Starting {} proxy for {} on port {}",
+        protocol,
+        source_url,
+        output_port
+    );
+
+    let https = https.unwrap_or(false);
+    let _reservation = {
+        let proxies = sessions.proxies.lock().await;
+        if let Some(existing) = proxies.get(&source_url) {
+            if existing.hls_url.starts_with("https://") != https {
+                return Err(format!(
+                    "A proxy for this stream is already running at {}; stop it before switching between HTTP and HTTPS",
+                    existing.hls_url
+                ).into());
+            }
+            log::info!("Reusing proxy on port {} for {}", existing.output_port, source_url);
+            return Ok(serde_json::json!({
+                "hlsUrl": existing.hls_url,
+                "status": "running"
+            }));
+        }
+        // Reserved while `proxies` is locked, so a concurrent start can't slip in between
+        StartReservation::acquire(&sessions, &source_url)?
+    };
+
+    let headers = headers.unwrap_or_default();
+    let hls_url = proxy_url(output_port, https);
+    let tls = match https {
+        true => Some(app.state::<LocalCert>().acceptor(&app)?),
+        false => None,
+    };
+
+    if protocol.eq_ignore_ascii_case("hls") {
+        let manifest_url = reqwest::Url::parse(&source_url)
+            .map_err(|e| format!("Invalid stream URL: {}", e))?;
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let listener = TcpListener::bind(("127.0.0.1", output_port))
+            .await
+            .map_err(|e| format!("Failed to bind port {}: {}", output_port, e))?;
+
+        let stop = CancellationToken::new();
+        let proxy_server = Arc::new(ProxyServer {
+            route: ProxyRoute::Relay(HlsRelay {
+                client,
+                manifest_url,
+                headers: header_map(&headers),
+                prefetch_count: prefetch_segments.map_or(0, usize::from),
+                cache: Arc::default(),
+                prefetch_stop: stop.clone(),
+            }),
+            allowed_origin: app.state::<SettingsStore>().get().proxy_allowed_origin,
+            stats: Arc::default(),
+            tls,
+        });
+        let server = supervise_server(app.clone(), source_url.clone(), listener, proxy_server.clone(), stop.clone());
+
+        log::info!("HLS relay for {} ready at {}", source_url, hls_url);
+
+        register_proxy(&sessions, source_url, ProxySession {
+            output_port,
+            hls_url: hls_url.clone(),
+            output_dir: None,
+            logs: LogBuffer::default(),
+            child: None,
+            server,
+            stop,
+            stats: proxy_server.stats.clone(),
+        }).await;
+
+        return Ok(serde_json::json!({
+            "hlsUrl": hls_url,
+            "status": "running"
+        }));
+    }
+
+    let rtsp_transport = rtsp_transport(&source_url, &protocol, transport.as_deref())?;
+    let ffmpeg_path = ffmpeg::require_ffmpeg()?;
+
+    // Pick the encoder, noting a fallback if the accelerator can't run here
+    let mut encoder = ffmpeg::SOFTWARE_ENCODER.to_string();
+    if let Some(requested) = transcode.as_ref().and_then(|t| t.video_encoder.as_deref()) {
+        let (resolved, fell_back) = ffmpeg::resolve_video_encoder(&ffmpeg_path, requested).await;
+        if fell_back {
+            let _ = app.emit("stream://encoder-fallback", EncoderFallbackEvent {
+                source_url: source_url.clone(),
+                requested: requested.to_string(),
+                used: resolved.clone(),
+            });
+        }
+        encoder = resolved;
+    }
+
+    // Bind before spawning FFmpeg so a busy port fails fast
+    let listener = TcpListener::bind(("127.0.0.1", output_port))
+        .await
+        .map_err(|e| format!("Failed to bind port {}: {}", output_port, e))?;
+
+    let rtsp_timeout = match rtsp_transport {
+        Some(_) => ffmpeg::rtsp_timeout_option(&ffmpeg_path).await,
+        None => "-timeout",
+    };
+    let output_dir = temp_dirs::allocate(&sessions, "proxy", &output_port.to_string()).await?;
+
+    let playlist = output_dir.join("index.m3u8");
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(), "-loglevel".into(), "warning".into(),
+    ];
+    args.extend(header_args(&source_url, &headers));
+    if let Some(transport) = rtsp_transport {
+        args.extend([
+            "-rtsp_transport".into(), transport.into(),
+            rtsp_timeout.into(), RTSP_TIMEOUT_US.to_string(),
+        ]);
+    }
+    args.extend(["-i".into(), source_url.clone()]);
+    args.extend(codec_args(transcode.as_ref(), &encoder));
+    args.extend([
+        "-f".into(), "hls".into(),
+        "-hls_time".into(), HLS_SEGMENT_SECS.to_string(),
+        "-hls_list_size".into(), "6".into(),
+        "-hls_flags".into(), "delete_segments".into(),
+        "-hls_segment_filename".into(), output_dir.join("segment_%05d.ts").to_string_lossy().to_string(),
+        playlist.to_string_lossy().to_string(),
+    ]);
+
+    log::info!("Executing FFmpeg: {} {}", ffmpeg_path, args.join(" "));
+
+    let mut command = Command::new(&ffmpeg_path);
+    command
+        .args(&args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            temp_dirs::release(&sessions, &output_dir).await;
+            return Err(format!("Failed to start FFmpeg: {}", e).into());
+        }
+    };
+
+    // Keep FFmpeg diagnostics and forward them to the frontend
+    let logs = LogBuffer::default();
+    let mut stderr_reader = None;
+    if let Some(stderr) = child.stderr.take() {
+        let app = app.clone();
+        let source_url = source_url.clone();
+        let logs = logs.clone();
+        stderr_reader = Some(tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let level = if line.to_lowercase().contains("error") {
+                    ConsoleLevel::Error
+                } else {
+                    ConsoleLevel::Info
+                };
+                console::record(level, "ffmpeg", line.clone());
+                logs.push(line.clone());
+                let _ = app.emit("stream://log", ProxyLogEvent {
+                    source_url: source_url.clone(),
+                    line,
+                });
+            }
+        }));
+    }
+
+    let proxy_server = Arc::new(ProxyServer {
+        route: ProxyRoute::Files(output_dir.clone()),
+        allowed_origin: app.state::<SettingsStore>().get().proxy_allowed_origin,
+        stats: Arc::default(),
+        tls,
+    });
+    let stop = CancellationToken::new();
+    let server = supervise_server(app.clone(), source_url.clone(), listener, proxy_server.clone(), stop.clone());
+
+    // Wait for the first playlist so the player doesn't start on a 404
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(STARTUP_TIMEOUT_SECS);
+    while !playlist.exists() {
+        let exited = child.try_wait().ok().flatten();
+        let timed_out = std::time::Instant::now() >= deadline;
+        if exited.is_some() || timed_out {
+            let _ = child.kill().await;
+            stop.cancel();
+            let _ = server.await;
+            temp_dirs::release(&sessions, &output_dir).await;
+
+            // Let the reader drain what FFmpeg wrote before it exited
+            if let Some(reader) = stderr_reader {
+                let _ = tokio::time::timeout(std::time::Duration::from_secs(1), reader).await;
+            }
+
+            let recent = logs.tail(STARTUP_ERROR_LINES);
+            let handshake_timed_out = recent.iter().any(|line| line.to_lowercase().contains("timed out"));
+            let reason = match (rtsp_transport, exited) {
+                (Some(transport), _) if handshake_timed_out || exited.is_none() => {
+                    let other = if transport == "tcp" { "udp" } else { "tcp" };
+                    format!(
+                        "RTSP handshake over {} did not complete (timed out). The camera may only accept {}, try protocol \"rtsp+{}\"",
+                        transport.to_uppercase(), other.to_uppercase(), other
+                    )
+                }
+                (_, Some(status)) => format!("FFmpeg exited before the stream started ({})", status),
+                (_, None) => format!("Timed out after {}s waiting for the stream to start", STARTUP_TIMEOUT_SECS),
+            };
+            console::record(ConsoleLevel::Error, "ffmpeg", format!("Proxy for {} failed: {}", source_url, reason));
+            return Err(if recent.is_empty() {
+                reason.into()
+            } else {
+                format!("{}:\n{}", reason, recent.join("\n")).into()
+            });
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+    }
+
+    log::info!("Proxy for {} ready at {} (encoder: {})", source_url, hls_url, encoder);
+
+    register_proxy(&sessions, source_url, ProxySession {
+        output_port,
+        hls_url: hls_url.clone(),
+        output_dir: Some(output_dir),
+        logs,
+        child: Some(child),
+        server,
+        stop,
+        stats: proxy_server.stats.clone(),
+    }).await;
+
+    Ok(serde_json::json!({
+        "hlsUrl": hls_url,
+        "status": "running",
+        "encoder": transcode.is_some().then_some(encoder)
+    }))
+}
+
+/// Stop the proxy for `source_url` and remove its segments
+#[tauri::command]
+pub async fn stop_stream_proxy(
+    sessions: State<'_, ActiveSessions>,
+    source_url: String,
+) -> Result<(), String> {
+    log::info!("Stopping proxy for {}", source_url);
+
+    let session = sessions.proxies.lock().await.remove(&source_url);
+    match session {
+        Some(session) => session.shutdown(&sessions).await,
+        None => log::warn!("No proxy running for {}", source_url),
+    }
+    Ok(())
+}
+
+/// Stop every proxy, on app exit
+pub async fn stop_all_proxies(sessions: &ActiveSessions) {
+    let proxies: Vec<ProxySession> = sessions.proxies.lock().await.drain().map(|(_, session)| session).collect();
+    for session in proxies {
+        session.shutdown(sessions).await;
+    }
+}
+
+/// Return the last `lines` FFmpeg stderr lines of a running proxy
+#[tauri::command]
+pub async fn get_proxy_logs(
+    sessions: State<'_, ActiveSessions>,
+    source_url: String,
+    lines: Option<usize>,
+) -> Result<Vec<String>, String> {
+    sessions.proxies.lock().await
+        .get(&source_url)
+        .map(|session| session.logs.tail(lines.unwrap_or(PROXY_LOG_LINES)))
+        .ok_or_else(|| format!("No proxy running for {}", source_url))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProxyRouteInfo {
+    pub source_url: String,
+    pub hls_url: String,
+    pub port: u16,
+    /// "relay" for upstream HLS, "ffmpeg" for converted streams
+    pub mode: String,
+    pub requests: u64,
+    pub bytes_served: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub bind_address: String,
+    pub ports: Vec<u16>,
+    pub active_routes: usize,
+    pub routes: Vec<ProxyRouteInfo>,
+    pub total_requests: u64,
+    pub total_bytes_served: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+/// Snapshot of every running proxy's embedded server, from in-memory counters only
+#[tauri::command]
+pub async fn get_server_info(sessions: State<'_, ActiveSessions>) -> Result<ServerInfo, String> {
+    let mut routes: Vec<ProxyRouteInfo> = sessions.proxies.lock().await.iter()
+        .map(|(source_url, session)| ProxyRouteInfo {
+            source_url: source_url.clone(),
+            hls_url: session.hls_url.clone(),
+            port: session.output_port,
+            mode: if session.child.is_some() { "ffmpeg" } else { "relay" }.to_string(),
+            requests: session.stats.requests.load(Ordering::Relaxed),
+            bytes_served: session.stats.bytes_served.load(Ordering::Relaxed),
+            cache_hits: session.stats.cache_hits.load(Ordering::Relaxed),
+            cache_misses: session.stats.cache_misses.load(Ordering::Relaxed),
+        })
+        .collect();
+    routes.sort_by_key(|route| route.port);
+
+    Ok(ServerInfo {
+        bind_address: "127.0.0.1".to_string(),
+        ports: routes.iter().map(|route| route.port).collect(),
+        active_routes: routes.len(),
+        total_requests: routes.iter().map(|route| route.requests).sum(),
+        total_bytes_served: routes.iter().map(|route| route.bytes_served).sum(),
+        cache_hits: routes.iter().map(|route| route.cache_hits).sum(),
+        cache_misses: routes.iter().map(|route| route.cache_misses).sum(),
+        routes,
+    })
+}
+
+/// Origin the check pretends to be, like the app's webview
+const TEST_ORIGIN: &str = APP_ORIGINS[0];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProxyEndpointReport {
+    pub reachable: bool,
+    pub status: Option<u16>,
+    pub allow_origin: Option<String>,
+    /// The returned `Access-Control-Allow-Origin` admits the app's origin
+    pub cors_ok: bool,
+    pub is_playlist: bool,
+    /// First media segment that was probed with a Range request
+    pub segment_url: Option<String>,
+    pub range_supported: Option<bool>,
+    pub error: Option<String>,
+}
+
+/// First URI line in a playlist, resolved against the playlist URL
+fn first_uri(playlist: &str, base: &reqwest::Url) -> Option<reqwest::Url> {
+    playlist.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .and_then(|uri| base.join(uri).ok())
+}
+
+/// Check that the local proxy on `output_port` answers, sends usable CORS headers
+/// and serves segments with byte ranges. A proxy started by the app is probed at its
+/// own URL, so HTTPS proxies are checked over TLS against the local certificate.
+#[tauri::command]
+pub async fn test_proxy_endpoint(
+    app: AppHandle,
+    sessions: State<'_, ActiveSessions>,
+    output_port: u16,
+) -> Result<ProxyEndpointReport, String> {
+    let hls_url = sessions.proxies.lock().await.values()
+        .find(|session| session.output_port == output_port)
+        .map(|session| session.hls_url.clone())
+        .unwrap_or_else(|| proxy_url(output_port, false));
+    let manifest_url = reqwest::Url::parse(&hls_url)
+        .map_err(|e| format!("Invalid URL: {}", e))?;
+
+    let mut builder = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(std::time::Duration::from_secs(5));
+    if manifest_url.scheme() == "https" {
+        builder = builder.add_root_certificate(crate::local_tls::certificate(&app)?);
+    }
+    let client = builder.build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut report = ProxyEndpointReport::default();
+    let response = match client.get(manifest_url.clone()).header("Origin", TEST_ORIGIN).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            report.error = Some(format!("Proxy on port {} is not reachable: {}", output_port, e));
+            return Ok(report);
+        }
+    };
+
+    report.reachable = true;
+    report.status = Some(response.status().as_u16());
+    report.allow_origin = response.headers()
+        .get(reqwest::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    report.cors_ok = report.allow_origin.as_deref().is_some_and(|o| o == "*" || o == TEST_ORIGIN);
+
+    let mut playlist = response.text().await.unwrap_or_default();
+    report.is_playlist = playlist.trim_start().starts_with("#EXTM3U");
+    if !report.is_playlist {
+        report.error = Some(format!("/stream/index.m3u8 returned status {} without a playlist", report.status.unwrap_or_default()));
+        return Ok(report);
+    }
+
+    // A master playlist points at variants, go one level down to reach segments
+    let mut segment = first_uri(&playlist, &manifest_url);
+    if playlist.contains("#EXT-X-STREAM-INF") {
+        if let Some(variant) = segment.take() {
+            playlist = match client.get(variant.clone()).send().await {
+                Ok(response) => response.text().await.unwrap_or_default(),
+                Err(_) => String::new(),
+            };
+            segment = first_uri(&playlist, &variant);
+        }
+    }
+
+    if let Some(segment) = segment {
+        report.segment_url = Some(segment.to_string());
+        report.range_supported = Some(match client.get(segment).header("Range", "bytes=0-0").send().await {
+            Ok(response) => {
+                response.status() == reqwest::StatusCode::PARTIAL_CONTENT
+                    && response.headers().contains_key(reqwest::header::CONTENT_RANGE)
+            }
+            Err(_) => false,
+        });
+    }
+
+    if !report.cors_ok {
+        report.error = Some(format!(
+            "Access-Control-Allow-Origin is {:?}, the player's requests will be blocked",
+            report.allow_origin
+        ));
+    }
+
+    Ok(report)
+}
+
+/// Set the origin the embedded server admits via CORS on top of the app's own webview:
+/// an origin like `https://player.example`, `"*"` to let any web page read streams
+/// through the proxy, or nothing for the app only (the default).
+/// Applies to proxies started afterwards.
+#[tauri::command]
+pub fn set_proxy_cors_origin(settings: State<'_, SettingsStore>, origin: Option<String>) -> Result<(), String> {
+    let origin = origin.map(|o| o.trim().to_string()).filter(|o| !o.is_empty());
+    if origin.as_deref().is_some_and(|o| o.contains(['\r', '\n'])) {
+        return Err("Invalid CORS origin".to_string());
+    }
+    log::info!("Setting proxy CORS origin to {}", origin.as_deref().unwrap_or("the app only"));
+    settings.update(|s| s.proxy_allowed_origin = origin)?;
+    Ok(())
+}
+
+/// `Access-Control-Allow-Origin` for a request from `request_origin`, `None` to send no
+/// CORS headers. The app's own origins are always admitted, `allowed` adds one more (or `*`).
+fn allow_origin(allowed: Option<&str>, request_origin: Option<&str>) -> Option<String> {
+    let is_app = |origin: &str| APP_ORIGINS.contains(&origin) || (cfg!(debug_assertions) && origin == DEV_ORIGIN);
+    match request_origin {
+        Some(origin) if is_app(origin) || allowed == Some(origin) => Some(origin.to_string()),
+        _ => allowed.filter(|allowed| *allowed == "*").map(|allowed| allowed.to_string()),
+    }
+}
+
+// ========================================
+// Embedded HLS file server
+// ========================================
+
+/// Run the embedded server for a proxy and restart it if it panics or stops accepting
+/// while the proxy is still running. The same port is rebound if possible, otherwise a
+/// free one, announced with `stream://port-changed`.
+fn supervise_server(
+    app: AppHandle,
+    source_url: String,
+    listener: TcpListener,
+    proxy_server: Arc<ProxyServer>,
+    stop: CancellationToken,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut port = listener.local_addr().map(|addr| addr.port()).unwrap_or_default();
+        let mut listener = Some(listener);
+        while let Some(current) = listener.take() {
+            let server = tauri::async_runtime::spawn(serve_hls(current, proxy_server.clone()));
+            let abort = server.abort_handle();
+            tokio::select! {
+                _ = stop.cancelled() => {
+                    abort.abort();
+                    return;
+                }
+                result = server => match result {
+                    Ok(()) => log::warn!("Proxy server for {} stopped accepting connections", source_url),
+                    Err(e) => log::warn!("Proxy server for {} died: {}", source_url, e),
+                },
+            }
+
+            // The old listener is gone, so the port is free unless something else took it
+            while !stop.is_cancelled() {
+                let rebound = match TcpListener::bind(("127.0.0.1", port)).await {
+                    Ok(rebound) => Ok(rebound),
+                    Err(_) => TcpListener::bind(("127.0.0.1", 0)).await,
+                };
+                match rebound {
+                    Ok(rebound) => {
+                        let new_port = rebound.local_addr().map(|addr| addr.port()).unwrap_or(port);
+                        if new_port != port {
+                            let hls_url = proxy_url(new_port, proxy_server.tls.is_some());
+                            if let Some(session) = app.state::<ActiveSessions>().proxies.lock().await.get_mut(&source_url) {
+                                session.output_port = new_port;
+                                session.hls_url = hls_url.clone();
+                            }
+                            console::record(ConsoleLevel::Warn, "proxy", format!(
+                                "Proxy for {} moved from port {} to {}", source_url, port, new_port
+                            ));
+                            let _ = app.emit("stream://port-changed", PortChangedEvent {
+                                source_url: source_url.clone(),
+                                old_port: port,
+                                new_port,
+                                hls_url,
+                            });
+                            port = new_port;
+                        }
+                        log::info!("Restarted proxy server for {} on port {}", source_url, port);
+                        listener = Some(rebound);
+                        break;
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to rebind proxy server for {}: {}", source_url, e);
+                        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        }
+    })
+}
+
+async fn serve_hls(listener: TcpListener, proxy_server: Arc<ProxyServer>) {
+    let mut consecutive_errors = 0;
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => {
+                consecutive_errors = 0;
+                stream
+            }
+            Err(e) => {
+                log::warn!("Proxy server accept failed: {}", e);
+                consecutive_errors += 1;
+                if consecutive_errors >= MAX_ACCEPT_ERRORS {
+                    return;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let proxy_server = proxy_server.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = match &proxy_server.tls {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(stream) => handle_connection(stream, &proxy_server).await,
+                    Err(e) => Err(e),
+                },
+                None => handle_connection(stream, &proxy_server).await,
+            };
+            if let Err(e) = result {
+                log::debug!("Proxy connection error: {}", e);
+            }
+        });
+    }
+}
+
+fn content_type_for(name: &str) -> &'static str {
+    match name.rsplit('.').next() {
+        Some("m3u8") => "application/vnd.apple.mpegurl",
+        Some("ts") => "video/mp2t",
+        Some("m4s") => "video/iso.segment",
+        Some("mp4") => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Only plain file names inside the session directory may be served
+pub fn is_safe_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        && !name.starts_with('.')
+}
+
+/// Local path that makes the relay fetch `url`
+fn relay_path(url: &str) -> String {
+    format!(
+        "/stream/relay?u={}",
+        url::form_urlencoded::byte_serialize(url.as_bytes()).collect::<String>()
+    )
+}
+
+/// Point every URI in an HLS playlist (segments, variants, keys, maps, renditions) at the
+/// relay. Also returns the upstream URLs it pointed there, the only ones the relay fetches.
+fn rewrite_manifest(body: &str, base: &reqwest::Url) -> (String, Vec<String>) {
+    let mut upstream = Vec::new();
+    let mut resolve = |uri: &str| match base.join(uri) {
+        Ok(absolute) if matches!(absolute.scheme(), "http" | "https") => {
+            let path = relay_path(absolute.as_str());
+            upstream.push(absolute.to_string());
+            path
+        }
+        _ => uri.to_string(),
+    };
+
+    let mut out = String::with_capacity(body.len());
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            out.push_str(line);
+        } else if trimmed.starts_with('#') {
+            // Tags like #EXT-X-KEY / #EXT-X-MEDIA carry URIs in a URI="..." attribute
+            match trimmed.find("URI=\"") {
+                Some(start) => {
+                    let value_start = start + 5;
+                    match trimmed[value_start..].find('"') {
+                        Some(len) => {
+                            out.push_str(&trimmed[..value_start]);
+                            out.push_str(&resolve(&trimmed[value_start..value_start + len]));
+                            out.push_str(&trimmed[value_start + len..]);
+                        }
+                        None => out.push_str(trimmed),
+                    }
+                }
+                None => out.push_str(trimmed),
+            }
+        } else {
+            out.push_str(&resolve(trimmed));
+        }
+        out.push('\n');
+    }
+    (out, upstream)
+}
+
+/// Read a response body, giving up once it passes `MAX_RELAY_BODY_BYTES`
+async fn read_limited(mut response: reqwest::Response) -> Result<Vec<u8>, String> {
+    if response.content_length().is_some_and(|len| len > MAX_RELAY_BODY_BYTES as u64) {
+        return Err(format!("body larger than {} bytes", MAX_RELAY_BODY_BYTES));
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if body.len() + chunk.len() > MAX_RELAY_BODY_BYTES {
+            return Err(format!("body larger than {} bytes", MAX_RELAY_BODY_BYTES));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// How to answer a `Range` header
+#[derive(Debug, PartialEq)]
+enum RangeRequest {
+    /// Inclusive byte range within the body
+    Satisfiable(usize, usize),
+    /// Unparsable or multiple ranges: serve the whole body with 200
+    Ignore,
+    /// Valid, but starts past the end of the body: 416
+    Unsatisfiable,
+}
+
+/// Parse a single `Range: bytes=...` spec against a body of `len` bytes
+fn parse_range(header: &str, len: usize) -> RangeRequest {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Ignore;
+    };
+    // Multiple ranges would need multipart responses; serve the whole body instead
+    if spec.contains(',') {
+        return RangeRequest::Ignore;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return RangeRequest::Ignore;
+    };
+    let parse = |value: &str| value.parse::<usize>().ok();
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => match parse(suffix) {
+            None => return RangeRequest::Ignore,
+            Some(0) => return RangeRequest::Unsatisfiable,
+            Some(_) if len == 0 => return RangeRequest::Unsatisfiable,
+            Some(suffix) => (len.saturating_sub(suffix), len - 1),
+        },
+        (start, "") => match parse(start) {
+            Some(start) => (start, usize::MAX),
+            None => return RangeRequest::Ignore,
+        },
+        (start, end) => match (parse(start), parse(end)) {
+            (Some(start), Some(end)) if start <= end => (start, end),
+            _ => return RangeRequest::Ignore,
+        },
+    };
+    if start >= len {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Satisfiable(start, end.min(len - 1))
+}
+
+/// Read one request or header line, refusing lines past `MAX_HEAD_LINE_BYTES`
+async fn read_head_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> std::io::Result<String> {
+    let mut line = String::new();
+    let read = (&mut *reader).take(MAX_HEAD_LINE_BYTES).read_line(&mut line).await?;
+    if read as u64 == MAX_HEAD_LINE_BYTES && !line.ends_with('\n') {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Request header line too long"));
+    }
+    Ok(line)
+}
+
+/// Request line plus the `Range` and `Origin` headers, the only ones that matter to us
+async fn read_request_head<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> std::io::Result<(String, Option<String>, Option<String>)> {
+    let request_line = read_head_line(reader).await?;
+    let mut range = None;
+    let mut origin = None;
+    for _ in 0..MAX_HEADER_LINES {
+        let line = read_head_line(reader).await?;
+        if line.trim().is_empty() {
+            return Ok((request_line, range, origin));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("range") {
+                range = Some(value.trim().to_string());
+            } else if name.trim().eq_ignore_ascii_case("origin") {
+                origin = Some(value.trim().to_string());
+            }
+        }
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Too many request headers"))
+}
+
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(stream: S, proxy_server: &ProxyServer) -> std::io::Result<()> {
+    let (read_half, mut write_half) = tokio::io::split(stream);
+    let mut reader = BufReader::new(read_half);
+
+    let timeout = std::time::Duration::from_secs(REQUEST_HEAD_TIMEOUT_SECS);
+    let (request_line, range, origin) = tokio::time::timeout(timeout, read_request_head(&mut reader)).await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "Request headers timed out"))??;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let cors_origin = allow_origin(proxy_server.allowed_origin.as_deref(), origin.as_deref());
+    let cors_origin = cors_origin.as_deref();
+    proxy_server.stats.requests.fetch_add(1, Ordering::Relaxed);
+
+    if method == "OPTIONS" {
+        return write_response(&mut write_half, cors_origin, "204 No Content", None, &[], &[], false).await;
+    }
+    if method != "GET" && method != "HEAD" {
+        return write_response(&mut write_half, cors_origin, "405 Method Not Allowed", None, &[], &[], false).await;
+    }
+
+    let response = match &proxy_server.route {
+        ProxyRoute::Files(root) => serve_file(root, path).await,
+        ProxyRoute::Relay(relay) => serve_relay(relay, &proxy_server.stats, path, query).await,
+    };
+
+    let Some((content_type, body)) = response else {
+        return write_response(&mut write_half, cors_origin, "404 Not Found", None, &[], &[], false).await;
+    };
+
+    let range = range.as_deref().map_or(RangeRequest::Ignore, |range| parse_range(range, body.len()));
+    let (status, body, extra_headers) = match range {
+        RangeRequest::Satisfiable(start, end) => (
+            "206 Partial Content",
+            body[start..=end].to_vec(),
+            vec![("Content-Range", format!("bytes {}-{}/{}", start, end, body.len()))],
+        ),
+        RangeRequest::Unsatisfiable => (
+            "416 Range Not Satisfiable",
+            Vec::new(),
+            vec![("Content-Range", format!("bytes */{}", body.len()))],
+        ),
+        RangeRequest::Ignore => ("200 OK", body, Vec::new()),
+    };
+    // HEAD gets the headers of the GET, including its Content-Length, but no body
+    let head_only = method == "HEAD";
+    if !head_only {
+        proxy_server.stats.bytes_served.fetch_add(body.len() as u64, Ordering::Relaxed);
+    }
+    write_response(&mut write_half, cors_origin, status, Some(&content_type), &body, &extra_headers, head_only).await
+}
+
+async fn serve_file(root: &Path, path: &str) -> Option<(String, Vec<u8>)> {
+    let name = path.strip_prefix("/stream/").filter(|name| is_safe_file_name(name))?;
+    let body = tokio::fs::read(root.join(name)).await.ok()?;
+    Some((content_type_for(name).to_string(), body))
+}
+
+async fn serve_relay(relay: &HlsRelay, stats: &ProxyStats, path: &str, query: &str) -> Option<(String, Vec<u8>)> {
+    let upstream = match path {
+        "/stream/index.m3u8" => relay.manifest_url.clone(),
+        "/stream/relay" => {
+            let target = url::form_urlencoded::parse(query.as_bytes())
+                .find(|(key, _)| key == "u")
+                .map(|(_, value)| value.into_owned())?;
+            // Only URLs from a manifest we rewrote, so pages can't use the proxy (and the
+            // channel's headers) to reach arbitrary hosts
+            let allowed = relay.cache.lock().unwrap_or_else(|e| e.into_inner()).relay_urls.contains(&target);
+            if !allowed {
+                log::warn!("Relay refused {}: not listed in a relayed manifest", target);
+                return None;
+            }
+            reqwest::Url::parse(&target).ok()?
+        }
+        _ => return None,
+    };
+
+    let cached = relay.cache.lock().unwrap_or_else(|e| e.into_inner())
+        .entries.get(upstream.as_str()).cloned();
+    if let Some(cached) = cached {
+        stats.cache_hits.fetch_add(1, Ordering::Relaxed);
+        schedule_prefetch(relay, upstream.as_str());
+        return Some(cached);
+    }
+
+    let response = relay.client.get(upstream.clone())
+        .headers(relay.headers.clone())
+        .send()
+        .await
+        .map_err(|e| log::warn!("Relay fetch failed for {}: {}", upstream, e))
+        .ok()?;
+    if !response.status().is_success() {
+        log::warn!("Relay upstream returned {} for {}", response.status(), upstream);
+        return None;
+    }
+
+    // Redirects change the base that relative URIs resolve against
+    let base = response.url().clone();
+    let content_type = response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_else(|| content_type_for(upstream.path()))
+        .to_string();
+    let body = read_limited(response).await
+        .map_err(|e| log::warn!("Relay read failed for {}: {}", upstream, e))
+        .ok()?;
+
+    if body.starts_with(b"#EXTM3U") {
+        let text = String::from_utf8_lossy(&body);
+        let (manifest, relayed) = rewrite_manifest(&text, &base);
+        {
+            let mut cache = relay.cache.lock().unwrap_or_else(|e| e.into_inner());
+            cache.allow(relayed);
+            if let Some(playlist) = media_segments(&text, &base) {
+                cache.playlists.insert(upstream.to_string(), playlist);
+            }
+        }
+        schedule_prefetch(relay, upstream.as_str());
+        Some(("application/vnd.apple.mpegurl".to_string(), manifest.into_bytes()))
+    } else {
+        stats.cache_misses.fetch_add(1, Ordering::Relaxed);
+        schedule_prefetch(relay, upstream.as_str());
+        Some((content_type, body))
+    }
+}
+
+/// Absolute segment URLs of a media playlist and whether it is VOD; `None` for master playlists
+fn media_segments(playlist: &str, base: &reqwest::Url) -> Option<(Vec<String>, bool)> {
+    if !playlist.contains("#EXTINF") {
+        return None;
+    }
+    let segments = playlist.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|uri| base.join(uri).ok())
+        .map(|url| url.to_string())
+        .collect();
+    let is_vod = playlist.contains("#EXT-X-ENDLIST") || playlist.contains("#EXT-X-PLAYLIST-TYPE:VOD");
+    Some((segments, is_vod))
+}
+
+/// Fetch the segments after `current` into the cache, one at a time, until the proxy stops
+fn schedule_prefetch(relay: &HlsRelay, current: &str) {
+    if relay.prefetch_count == 0 {
+        return;
+    }
+    let targets: Vec<String> = {
+        let mut cache = relay.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.is_segment(current) {
+            cache.last_served = Some(current.to_string());
+        }
+        let targets: Vec<String> = cache.upcoming(current, relay.prefetch_count)
+            .into_iter()
+            .filter(|url| !cache.entries.contains_key(url) && !cache.in_flight.contains(url))
+            .collect();
+        cache.in_flight.extend(targets.iter().cloned());
+        targets
+    };
+    if targets.is_empty() {
+        return;
+    }
+
+    let client = relay.client.clone();
+    let headers = relay.headers.clone();
+    let cache = relay.cache.clone();
+    let stop = relay.prefetch_stop.clone();
+    tauri::async_runtime::spawn(async move {
+        for url in &targets {
+            let fetch = async {
+                let response = client.get(url).headers(headers.clone()).send().await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| e.to_string())?;
+                let content_type = response.headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_else(|| content_type_for(response.url().path()))
+                    .to_string();
+                Ok::<_, String>((content_type, read_limited(response).await?))
+            };
+            let result = tokio::select! {
+                _ = stop.cancelled() => break,
+                result = fetch => result,
+            };
+            let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+            cache.in_flight.remove(url);
+            match result {
+                Ok((content_type, body)) => cache.insert(url.clone(), content_type, body),
+                Err(e) => log::debug!("Prefetch failed for {}: {}", url, e),
+            }
+        }
+        let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+        for url in &targets {
+            cache.in_flight.remove(url);
+        }
+    });
+}
+
+async fn write_response<W: AsyncWriteExt + Unpin>(
+    writer: &mut W,
+    cors_origin: Option<&str>,
+    status: &str,
+    content_type: Option<&str>,
+    body: &[u8],
+    extra_headers: &[(&str, String)],
+    head_only: bool,
+) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nCache-Control: no-cache\r\nConnection: close\r\n",
+        status,
+        body.len()
+    );
+    if let Some(cors_origin) = cors_origin {
+        head.push_str(&format!(
+            "Access-Control-Allow-Origin: {}\r\nAccess-Control-Allow-Methods: GET, HEAD, OPTIONS\r\nAccess-Control-Allow-Headers: *\r\nAccess-Control-Expose-Headers: Content-Length, Content-Range\r\n",
+            cors_origin
+        ));
+    }
+    // The answer depends on the request's Origin unless any origin is allowed
+    if cors_origin != Some("*") {
+        head.push_str("Vary: Origin\r\n");
+    }
+    if let Some(content_type) = content_type {
+        head.push_str(&format!("Content-Type: {}\r\n", content_type));
+    }
+    for (name, value) in extra_headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    writer.write_all(head.as_bytes()).await?;
+    if !head_only {
+        writer.write_all(body).await?;
+    }
+    writer.flush().await
+}
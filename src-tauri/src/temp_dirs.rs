@@ -0,0 +1,172 @@
+// ========================================
+// Temp Directories
+// ========================================
+//
+// Everything the app writes to the temp folder lives under one root per user:
+// per-session scratch directories (FFmpeg HLS output) under `sessions/` and tunnel
+// configs under `tunnels/`. Session directories are tracked in
+// `ActiveSessions::temp_dirs` and removed when their session stops or the app exits;
+// ones left behind by a crash are swept on the next startup.
+
+use crate::sessions::ActiveSessions;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const ROOT_DIR: &str = "claudetv";
+const SESSIONS_DIR: &str = "sessions";
+
+/// Session directories untouched for this long at startup belong to no live process
+const ORPHAN_MAX_AGE_SECS: u64 = 6 * 60 * 60;
+
+/// HLS output directories older versions created directly in the temp folder
+const LEGACY_PROXY_PREFIX: &str = "claudetv-proxy-";
+
+/// The current user's root under the temp folder (not created). The Unix temp folder
+/// is shared by all users, so the name carries the uid.
+pub fn root() -> PathBuf {
+    #[cfg(unix)]
+    let name = format!("{}-{}", ROOT_DIR, current_uid());
+    #[cfg(not(unix))]
+    let name = ROOT_DIR.to_string();
+    std::env::temp_dir().join(name)
+}
+
+#[cfg(unix)]
+pub(crate) fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+/// Create the root if needed and check that it is a real directory only the current
+/// user can use, so one planted by another user (or a symlink) is never written into
+pub fn ensure_root() -> Result<PathBuf, String> {
+    let root = root();
+    match std::fs::create_dir(&root) {
+        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => {
+            return Err(format!("Failed to create {}: {}", root.display(), e));
+        }
+        _ => {}
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let metadata = std::fs::symlink_metadata(&root)
+            .map_err(|e| format!("Failed to inspect {}: {}", root.display(), e))?;
+        if !metadata.is_dir() || metadata.uid() != current_uid() {
+            return Err(format!("{} is not a directory owned by the current user, refusing to use it", root.display()));
+        }
+        if metadata.mode() & 0o777 != 0o700 {
+            std::fs::set_permissions(&root, std::fs::Permissions::from_mode(0o700))
+                .map_err(|e| format!("Failed to restrict {}: {}", root.display(), e))?;
+        }
+    }
+    Ok(root)
+}
+
+/// True if the current user owns `path` (always true where there are no uids)
+fn owned_by_current_user(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::symlink_metadata(path).is_ok_and(|m| m.uid() == current_uid())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        true
+    }
+}
+
+/// Allocate an empty private directory for a session and track it until `release`.
+/// `kind` and `id` name the directory, e.g. "proxy" and the output port.
+pub async fn allocate(sessions: &ActiveSessions, kind: &str, id: &str) -> Result<PathBuf, String> {
+    let name: String = format!("{}-{}", kind, id)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let sessions_dir = ensure_root()?.join(SESSIONS_DIR);
+    crate::private_files::create_private_dir(&sessions_dir)?;
+
+    // Anything already there is left from a session that is gone
+    let dir = sessions_dir.join(name);
+    remove(&dir).await;
+    crate::private_files::create_private_dir(&dir)?;
+    sessions.temp_dirs.lock().await.insert(dir.clone());
+    Ok(dir)
+}
+
+/// Remove a directory from `allocate` and stop tracking it
+pub async fn release(sessions: &ActiveSessions, dir: &Path) {
+    sessions.temp_dirs.lock().await.remove(dir);
+    remove(dir).await;
+}
+
+/// Remove every tracked directory, on app exit
+pub async fn release_all(sessions: &ActiveSessions) {
+    let dirs: Vec<PathBuf> = sessions.temp_dirs.lock().await.drain().collect();
+    for dir in dirs {
+        remove(&dir).await;
+    }
+}
+
+async fn remove(dir: &Path) {
+    if let Err(e) = tokio::fs::remove_dir_all(dir).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// Latest modification of `dir` or a file directly in it. Files rewritten in place
+/// (playlists, status files) don't touch the directory's own time.
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+    let own = std::fs::metadata(dir).and_then(|m| m.modified()).ok()?;
+    let newest_entry = std::fs::read_dir(dir).ok()?
+        .flatten()
+        .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .max();
+    Some(newest_entry.map_or(own, |entry| entry.max(own)))
+}
+
+/// Remove session directories left by a crash: anything under `sessions/` (or a legacy
+/// `claudetv-proxy-*` directory of the current user) not modified for
+/// `ORPHAN_MAX_AGE_SECS`. The age check keeps the directories of another running
+/// instance. Returns how many were removed.
+pub fn sweep_orphans() -> usize {
+    let mut candidates: Vec<PathBuf> = match ensure_root() {
+        Ok(root) => std::fs::read_dir(root.join(SESSIONS_DIR))
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default(),
+        Err(e) => {
+            log::warn!("Skipping temp directory sweep: {}", e);
+            Vec::new()
+        }
+    };
+    if let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) {
+        candidates.extend(entries.flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(LEGACY_PROXY_PREFIX))
+            .map(|entry| entry.path())
+            .filter(|path| owned_by_current_user(path)));
+    }
+
+    let max_age = Duration::from_secs(ORPHAN_MAX_AGE_SECS);
+    let mut removed = 0;
+    // symlink_metadata so a link is never followed out of the temp folder
+    let is_dir = |path: &PathBuf| std::fs::symlink_metadata(path).is_ok_and(|m| m.is_dir());
+    for dir in candidates.into_iter().filter(is_dir) {
+        let stale = last_modified(&dir)
+            .and_then(|time| time.elapsed().ok())
+            .is_some_and(|age| age >= max_age);
+        if !stale {
+            continue;
+        }
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Failed to remove orphaned {}: {}", dir.display(), e),
+        }
+    }
+    if removed > 0 {
+        log::info!("Removed {} orphaned temp directories", removed);
+    }
+    removed
+}
@@ -0,0 +1,131 @@
+// ========================================
+// Throughput Measurement
+// ========================================
+
+use crate::http_client::SharedHttpClient;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+/// Served by Cloudflare's speed test, large enough to outlast the default duration
+const DEFAULT_TEST_URL: &str = "https://speed.cloudflare.com/__down?bytes=100000000";
+
+const DEFAULT_DURATION_SECS: u64 = 10;
+const MAX_DURATION_SECS: u64 = 60;
+
+/// Stop after this many bytes even if time is left
+const MAX_TEST_BYTES: u64 = 200 * 1024 * 1024;
+
+/// Minimum time between progress events
+const PROGRESS_INTERVAL_MS: u128 = 250;
+
+#[derive(Debug, Clone, Serialize)]
+struct ThroughputProgressEvent {
+    bytes: u64,
+    elapsed_ms: u64,
+    mbps: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ThroughputStop {
+    Duration,
+    SizeCap,
+    /// The server sent the whole body before either limit
+    Complete,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThroughputReport {
+    pub test_url: String,
+    pub bytes: u64,
+    /// Time spent receiving the body, from response headers to the stop
+    pub elapsed_ms: u64,
+    pub mbps: f64,
+    pub time_to_first_byte_ms: u64,
+    pub stopped_by: ThroughputStop,
+}
+
+fn mbps(bytes: u64, elapsed: std::time::Duration) -> f64 {
+    bytes as f64 * 8.0 / elapsed.as_secs_f64().max(0.001) / 1_000_000.0
+}
+
+/// Download from `test_url` through the shared client (same proxy and routing as
+/// `http_fetch`) for `duration_secs` and report the average rate.
+/// Emits `throughput://progress` while running.
+#[tauri::command]
+pub async fn measure_throughput(
+    app: AppHandle,
+    shared_client: State<'_, SharedHttpClient>,
+    test_url: Option<String>,
+    duration_secs: Option<u64>,
+) -> Result<ThroughputReport, String> {
+    let test_url = test_url.unwrap_or_else(|| DEFAULT_TEST_URL.to_string());
+    let duration_secs = duration_secs.unwrap_or(DEFAULT_DURATION_SECS).clamp(1, MAX_DURATION_SECS);
+    let parsed = url::Url::parse(&test_url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!("Unsupported URL scheme: {}", parsed.scheme()));
+    }
+
+    log::info!("Measuring throughput from {} for {}s", test_url, duration_secs);
+
+    let requested = std::time::Instant::now();
+    // Headroom over the measurement so the client timeout doesn't cut it short
+    let mut response = shared_client.get()
+        .get(parsed)
+        .timeout(std::time::Duration::from_secs(duration_secs + 15))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Test endpoint returned status {}", response.status()));
+    }
+    let time_to_first_byte_ms = requested.elapsed().as_millis() as u64;
+
+    let limit = std::time::Duration::from_secs(duration_secs);
+    let started = std::time::Instant::now();
+    let mut last_progress = started;
+    let mut bytes: u64 = 0;
+    let stopped_by = loop {
+        let remaining = limit.saturating_sub(started.elapsed());
+        let chunk = match tokio::time::timeout(remaining, response.chunk()).await {
+            Err(_) => break ThroughputStop::Duration,
+            Ok(chunk) => chunk.map_err(|e| format!("Download interrupted after {} bytes: {}", bytes, e))?,
+        };
+        let Some(chunk) = chunk else {
+            break ThroughputStop::Complete;
+        };
+        bytes += chunk.len() as u64;
+        if bytes >= MAX_TEST_BYTES {
+            break ThroughputStop::SizeCap;
+        }
+
+        if last_progress.elapsed().as_millis() >= PROGRESS_INTERVAL_MS {
+            last_progress = std::time::Instant::now();
+            let _ = app.emit("throughput://progress", ThroughputProgressEvent {
+                bytes,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                mbps: mbps(bytes, started.elapsed()),
+            });
+        }
+    };
+
+    let elapsed = started.elapsed();
+    let report = ThroughputReport {
+        test_url,
+        bytes,
+        elapsed_ms: elapsed.as_millis() as u64,
+        mbps: mbps(bytes, elapsed),
+        time_to_first_byte_ms,
+        stopped_by,
+    };
+    let _ = app.emit("throughput://progress", ThroughputProgressEvent {
+        bytes,
+        elapsed_ms: report.elapsed_ms,
+        mbps: report.mbps,
+    });
+
+    log::info!(
+        "Throughput: {:.1} Mbps ({} bytes in {} ms, stopped by {:?})",
+        report.mbps, report.bytes, report.elapsed_ms, report.stopped_by
+    );
+    Ok(report)
+}
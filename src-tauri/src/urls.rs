@@ -0,0 +1,76 @@
+// ========================================
+// URL Normalization
+// ========================================
+
+/// Scheme assumed when the input has none; IPTV panels are mostly plain HTTP
+const DEFAULT_SCHEME: &str = "http";
+
+/// Schemes that must have a host
+const HOST_SCHEMES: &[&str] = &["http", "https", "rtsp", "rtsps", "rtmp", "rtmps", "srt", "udp", "rtp"];
+
+/// `%` that doesn't start an escape sequence, encoded so it survives as a literal
+fn escape_stray_percent(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = String::with_capacity(input.len());
+    for (i, c) in input.char_indices() {
+        let escape = c == '%'
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_hexdigit)
+            && bytes.get(i + 2).is_some_and(u8::is_ascii_hexdigit);
+        if c == '%' && !escape {
+            out.push_str("%25");
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Clean up a URL typed or pasted by the user: trim it and any surrounding quotes or
+/// `<>`, add `http://` when there is no scheme, percent-encode characters that aren't
+/// allowed (spaces, non-ASCII, stray `%`) and check that it parses with a host.
+#[tauri::command]
+pub fn normalize_url(input: String) -> Result<String, String> {
+    let trimmed = input.trim()
+        .trim_start_matches(['"', '\'', '<'])
+        .trim_end_matches(['"', '\'', '>'])
+        .trim();
+    if trimmed.is_empty() {
+        return Err("URL is empty".to_string());
+    }
+
+    // Look for "://" rather than parsing, since "host:8080/path" would parse with "host" as the scheme
+    let with_scheme = match trimmed.split_once("://") {
+        Some((scheme, _)) => {
+            let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+            if !valid {
+                return Err(format!("Invalid URL scheme '{}'", scheme));
+            }
+            trimmed.to_string()
+        }
+        None => match trimmed.strip_prefix("//") {
+            Some(rest) => format!("{}://{}", DEFAULT_SCHEME, rest),
+            None => format!("{}://{}", DEFAULT_SCHEME, trimmed),
+        },
+    };
+
+    // The parser percent-encodes spaces and other illegal characters in the path and query
+    let url = url::Url::parse(&escape_stray_percent(&with_scheme)).map_err(|e| match e {
+        url::ParseError::EmptyHost => format!("URL '{}' has no host", trimmed),
+        url::ParseError::InvalidPort => format!("URL '{}' has an invalid port (expected 1-65535)", trimmed),
+        url::ParseError::InvalidIpv4Address | url::ParseError::InvalidIpv6Address => {
+            format!("URL '{}' has an invalid IP address", trimmed)
+        }
+        url::ParseError::InvalidDomainCharacter | url::ParseError::IdnaError => {
+            format!("URL '{}' has invalid characters in the host name", trimmed)
+        }
+        other => format!("Invalid URL '{}': {}", trimmed, other),
+    })?;
+
+    let scheme = url.scheme().to_string();
+    if HOST_SCHEMES.contains(&scheme.as_str()) && url.host_str().map_or(true, str::is_empty) {
+        return Err(format!("URL '{}' has no host", trimmed));
+    }
+    Ok(url.to_string())
+}
+
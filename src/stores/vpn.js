@@ -181,7 +181,7 @@ export const useVpnStore = defineStore("vpn", () => {
             return true;
         } catch (e) {
             console.error("[VPN] Connection failed:", e);
-            error.value = e.toString();
+            error.value = e?.message ?? e.toString();
             status.value = VpnStatus.Error;
             return false;
         } finally {
@@ -229,7 +229,7 @@ export const useVpnStore = defineStore("vpn", () => {
             return true;
         } catch (e) {
             console.error("[VPN] Disconnect failed:", e);
-            error.value = e.toString();
+            error.value = e?.message ?? e.toString();
             status.value = VpnStatus.Error;
             return false;
         } finally {
@@ -89,7 +89,7 @@ export async function httpFetch(url, options = {}) {
         return response;
     } catch (error) {
         console.error('[httpClient] Request failed:', error);
-        throw new Error(`HTTP request failed: ${error}`);
+        throw new Error(`HTTP request failed: ${error?.message ?? error}`);
     }
 }
 